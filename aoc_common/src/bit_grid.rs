@@ -0,0 +1,194 @@
+use std::fmt::{self, Display};
+
+use super::input;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+// Grid of booleans with every row packed into u64 words. Bit `x % 64` of word `x / 64` is column x
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+
+        Self {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    // Cells for which `is_set` returns true are turned on. Blank lines are ignored
+    pub fn parse(text: &str, is_set: impl Fn(char) -> bool) -> Self {
+        let lines = input::lines(text)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut grid = Self::new(width, lines.len());
+
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if is_set(c) {
+                    grid.set(x, y, true);
+                }
+            }
+        }
+
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        assert!(x < self.width && y < self.height, "({x}, {y}) out of grid");
+
+        self.row(y)[x / WORD_BITS] & (1 << (x % WORD_BITS)) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        assert!(x < self.width && y < self.height, "({x}, {y}) out of grid");
+
+        let word = &mut self.row_mut(y)[x / WORD_BITS];
+        let mask = 1 << (x % WORD_BITS);
+
+        if value {
+            *word |= mask;
+        } else {
+            *word &= !mask;
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    // Number of cells that differ between two rows
+    pub fn row_differences(&self, y1: usize, y2: usize) -> usize {
+        self.row(y1)
+            .iter()
+            .zip(self.row(y2))
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum()
+    }
+
+    // Lowest column where two rows differ
+    pub fn first_row_difference(&self, y1: usize, y2: usize) -> Option<usize> {
+        self.row(y1)
+            .iter()
+            .zip(self.row(y2))
+            .enumerate()
+            .find(|(_, (a, b))| a != b)
+            .map(|(i, (a, b))| i * WORD_BITS + (a ^ b).trailing_zeros() as usize)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn iter_ones(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height).flat_map(move |y| {
+            (0..self.width)
+                .filter(move |&x| self.get(x, y))
+                .map(move |x| (x, y))
+        })
+    }
+
+    // Columns become rows, so column comparisons can use the row methods
+    pub fn transpose(&self) -> Self {
+        let mut transposed = Self::new(self.height, self.width);
+
+        for (x, y) in self.iter_ones() {
+            transposed.set(y, x, true);
+        }
+
+        transposed
+    }
+}
+
+impl Display for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                write!(f, "{}", if self.get(x, y) { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_query() {
+        let grid = BitGrid::parse("#..#\n.##.\n#...", |c| c == '#');
+
+        assert_eq!(4, grid.width());
+        assert_eq!(3, grid.height());
+        assert!(grid.get(0, 0));
+        assert!(!grid.get(1, 0));
+        assert!(grid.get(2, 1));
+        assert_eq!(5, grid.count_ones());
+        assert_eq!(4, grid.row_differences(0, 1));
+        assert_eq!(1, grid.row_differences(0, 2));
+        assert_eq!(Some(3), grid.first_row_difference(0, 2));
+        assert_eq!(None, grid.first_row_difference(1, 1));
+        assert_eq!("#..#\n.##.\n#...\n", grid.to_string());
+    }
+
+    #[test]
+    fn transpose() {
+        let grid = BitGrid::parse("#..#\n.##.\n#...", |c| c == '#');
+        let transposed = grid.transpose();
+
+        assert_eq!("#.#\n.#.\n.#.\n#..\n", transposed.to_string());
+        assert_eq!(grid, transposed.transpose());
+    }
+
+    #[test]
+    fn wide_rows() {
+        let mut grid = BitGrid::new(131, 2);
+        grid.set(0, 0, true);
+        grid.set(64, 0, true);
+        grid.set(130, 1, true);
+
+        assert_eq!(3, grid.row(0).len());
+        assert!(grid.get(64, 0));
+        assert!(!grid.get(63, 0));
+        assert_eq!(3, grid.row_differences(0, 1));
+        assert_eq!(Some(0), grid.first_row_difference(0, 1));
+
+        grid.set(0, 0, false);
+        grid.set(64, 0, false);
+        assert_eq!(Some(130), grid.first_row_difference(0, 1));
+        assert_eq!(
+            vec![(1, 130)],
+            grid.transpose().iter_ones().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use toml::{Table, Value};
+
+use super::explain::{Explain, Step};
+use super::parse::{number, ParseError, ParseResult};
+use super::solution::{Entry, Puzzle, Registry};
+
+const PARTS: [(u8, &str); 2] = [(1, "part_one"), (2, "part_two")];
+
+// Known answers keyed by (year, day, part), read from a file shaped like:
+//   [2023.01]
+//   part_one = 54916
+//   part_two = "also a string"
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    answers: BTreeMap<(u16, u8, u8), String>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> ParseResult<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| ParseError::new(format!("Could not read {}: {e}", path.display())))?;
+
+        Self::parse(&text).map_err(|e| ParseError::new(format!("{}: {e}", path.display())))
+    }
+
+    pub fn parse(text: &str) -> ParseResult<Self> {
+        let table = text
+            .parse::<Table>()
+            .map_err(|e| ParseError::new(format!("Invalid answer manifest: {e}")))?;
+        let mut answers = BTreeMap::new();
+
+        for (year, days) in &table {
+            let year = number(year)?;
+
+            for (day, parts) in as_table(days, year)? {
+                let day = number(day)?;
+
+                for (part, answer) in as_table(parts, year)? {
+                    let part = PARTS
+                        .iter()
+                        .find(|(_, name)| name == part)
+                        .ok_or_else(|| {
+                            ParseError::new(format!("Unknown part {part} in {year} day {day}"))
+                        })?
+                        .0;
+                    let answer = match answer {
+                        Value::String(answer) => answer.clone(),
+                        Value::Integer(answer) => answer.to_string(),
+                        other => {
+                            return Err(ParseError::new(format!(
+                                "Invalid answer {other} in {year} day {day}"
+                            )))
+                        }
+                    };
+
+                    answers.insert((year, day, part), answer);
+                }
+            }
+        }
+
+        Ok(Self { answers })
+    }
+
+    // Answers from `other` win over the ones already here
+    pub fn merge(&mut self, other: Manifest) {
+        self.answers.extend(other.answers);
+    }
+
+    pub fn get(&self, year: u16, day: u8, part: u8) -> Option<&str> {
+        self.answers.get(&(year, day, part)).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+}
+
+fn as_table(value: &Value, year: u16) -> ParseResult<&Table> {
+    value
+        .as_table()
+        .ok_or_else(|| ParseError::new(format!("Expected a table under {year}")))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail { expected: String },
+    // The manifest has no answer for this part yet
+    Missing,
+    // There is no input file to run the solution on
+    NoInput,
+    // The input file didn't parse
+    Invalid { error: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub puzzle: Puzzle,
+    pub part: u8,
+    pub answer: Option<String>,
+    pub status: Status,
+    // Parsing included, as each part is run from the raw input
+    pub elapsed: Duration,
+    // What the solution explained, when asked to
+    pub steps: Vec<Step>,
+}
+
+// Runs both parts of a day on its stored input. A part the day doesn't have is only reported
+// when the manifest expects an answer for it
+pub fn check_entry(entry: &Entry, manifest: &Manifest, explain: bool) -> Vec<Outcome> {
+    let puzzle = entry.puzzle;
+    let input = fs::read_to_string(puzzle.input_path()).ok();
+    let mut outcomes = Vec::new();
+
+    for (part, _) in PARTS {
+        let expected = manifest.get(puzzle.year, puzzle.day, part);
+
+        let Some(input) = &input else {
+            outcomes.push(Outcome {
+                puzzle,
+                part,
+                answer: None,
+                status: Status::NoInput,
+                elapsed: Duration::ZERO,
+                steps: Vec::new(),
+            });
+            continue;
+        };
+
+        let mut explain = Explain::new(explain);
+        let start = Instant::now();
+        let answer = entry.run(input, part, &mut explain);
+        let elapsed = start.elapsed();
+        let steps = explain.into_steps();
+
+        let answer = match answer {
+            Ok(answer) => answer,
+            Err(error) => {
+                outcomes.push(Outcome {
+                    puzzle,
+                    part,
+                    answer: None,
+                    status: Status::Invalid {
+                        error: error.to_string(),
+                    },
+                    elapsed,
+                    steps,
+                });
+                continue;
+            }
+        };
+
+        let status = match (expected, &answer) {
+            (None, None) => continue,
+            (None, Some(_)) => Status::Missing,
+            (Some(expected), Some(answer)) if expected == answer => Status::Pass,
+            (Some(expected), _) => Status::Fail {
+                expected: expected.to_owned(),
+            },
+        };
+
+        outcomes.push(Outcome {
+            puzzle,
+            part,
+            answer,
+            status,
+            elapsed,
+            steps,
+        });
+    }
+
+    outcomes
+}
+
+pub fn check(registry: &Registry, manifest: &Manifest) -> Vec<Outcome> {
+    registry
+        .entries()
+        .iter()
+        .flat_map(|entry| check_entry(entry, manifest, false))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::super::solution::{Solution, Visitor};
+    use super::*;
+
+    struct Sum;
+
+    impl Solution for Sum {
+        type Parsed<'a> = Vec<usize>;
+
+        fn parse(input: &str) -> ParseResult<Self::Parsed<'_>> {
+            input.trim().split(',').map(number).collect()
+        }
+
+        fn part_one(parsed: &Self::Parsed<'_>, explain: &mut Explain) -> String {
+            explain.step("terms", || format!("{parsed:?}"));
+            parsed.iter().sum::<usize>().to_string()
+        }
+
+        fn part_two(parsed: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+            Some(parsed.iter().product::<usize>().to_string())
+        }
+    }
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = Manifest::parse(
+            "
+            [2023.01]
+            part_one = 54916
+            part_two = \"abc\"
+
+            [2024.25]
+            part_one = 3
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(3, manifest.len());
+        assert_eq!(Some("54916"), manifest.get(2023, 1, 1));
+        assert_eq!(Some("abc"), manifest.get(2023, 1, 2));
+        assert_eq!(Some("3"), manifest.get(2024, 25, 1));
+        assert_eq!(None, manifest.get(2024, 25, 2));
+
+        let mut merged =
+            Manifest::parse("[2023.01]\npart_one = 1\n\n[2023.02]\npart_one = 2").unwrap();
+        merged.merge(manifest);
+
+        assert_eq!(4, merged.len());
+        assert_eq!(Some("54916"), merged.get(2023, 1, 1));
+        assert_eq!(Some("2"), merged.get(2023, 2, 1));
+    }
+
+    #[test]
+    fn bad_manifests() {
+        assert_eq!(
+            Err(ParseError::new("Unknown part part_three in 2023 day 1")),
+            Manifest::parse("[2023.01]\npart_three = 1")
+        );
+        assert_eq!(
+            Err(ParseError::new("Invalid answer 1.5 in 2023 day 1")),
+            Manifest::parse("[2023.01]\npart_one = 1.5")
+        );
+        assert_eq!(
+            Err(ParseError::new("Expected a table under 2023")),
+            Manifest::parse("[2023]\n01 = 1")
+        );
+        assert_eq!(
+            Err(ParseError::new("Invalid number \"twenty\"")),
+            Manifest::parse("[twenty.01]\npart_one = 1")
+        );
+        assert!(Manifest::parse("[2023.01\npart_one = 1").is_err());
+        assert!(Manifest::load(Path::new("no/such/answers.toml")).is_err());
+    }
+
+    #[test]
+    fn check_outcomes() {
+        let res_dir = env::temp_dir().join(format!("check_outcomes_{}", std::process::id()));
+        fs::create_dir_all(&res_dir).unwrap();
+        fs::write(res_dir.join("day_01.txt"), "2,3,4\n").unwrap();
+        let res_dir: &'static str = res_dir.to_str().unwrap().to_owned().leak();
+
+        let mut registry = Registry::new();
+        registry.visit::<Sum>(Puzzle {
+            year: 2023,
+            day: 1,
+            res_dir,
+        });
+        registry.visit::<Sum>(Puzzle {
+            year: 2023,
+            day: 2,
+            res_dir,
+        });
+
+        let manifest =
+            Manifest::parse("[2023.01]\npart_one = 9\n\n[2023.02]\npart_one = 1").unwrap();
+        let statuses = check(&registry, &manifest)
+            .into_iter()
+            .map(|outcome| (outcome.puzzle.day, outcome.part, outcome.status))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (1, 1, Status::Pass),
+                (1, 2, Status::Missing),
+                (2, 1, Status::NoInput),
+                (2, 2, Status::NoInput),
+            ],
+            statuses
+        );
+
+        let manifest = Manifest::parse("[2023.01]\npart_one = 10").unwrap();
+        let outcome = &check_entry(&registry.entries()[0], &manifest, true)[0];
+
+        assert_eq!(Some("9"), outcome.answer.as_deref());
+        assert_eq!("terms: [2, 3, 4]", outcome.steps[0].to_string());
+        assert_eq!(
+            Status::Fail {
+                expected: "10".to_string()
+            },
+            outcome.status
+        );
+
+        fs::write(Path::new(res_dir).join("day_01.txt"), "2,x\n").unwrap();
+        let outcome = &check_entry(&registry.entries()[0], &manifest, false)[0];
+
+        assert_eq!(None, outcome.answer);
+        assert_eq!(
+            Status::Invalid {
+                error: "Invalid number \"x\"".to_string()
+            },
+            outcome.status
+        );
+
+        fs::remove_dir_all(res_dir).unwrap();
+    }
+}
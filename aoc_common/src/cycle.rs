@@ -0,0 +1,120 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Cycle {
+    // Steps before entering the cycle
+    pub mu: usize,
+    // Length of the cycle
+    pub lambda: usize,
+}
+
+impl Cycle {
+    // Smallest number of steps that leads to the same state as `steps`
+    pub fn equivalent_steps(&self, steps: usize) -> usize {
+        if steps < self.mu {
+            steps
+        } else {
+            self.mu + (steps - self.mu) % self.lambda
+        }
+    }
+}
+
+// Brent's algorithm. States are compared by their hash, so only the current ones are kept around
+pub fn find_cycle<T, F>(start: T, mut next: F) -> Cycle
+where
+    T: Hash,
+    F: FnMut(&T) -> T,
+{
+    let start_hash = hash(&start);
+
+    // Find lambda by searching successive powers of two
+    let mut power = 1;
+    let mut lambda = 1;
+    let mut tortoise = start_hash;
+    let mut hare_state = next(&start);
+    let mut hare = hash(&hare_state);
+
+    while tortoise != hare {
+        if power == lambda {
+            tortoise = hare;
+            power *= 2;
+            lambda = 0;
+        }
+        hare_state = next(&hare_state);
+        hare = hash(&hare_state);
+        lambda += 1;
+    }
+
+    // Find mu with the hare lambda steps ahead of the tortoise
+    let mut tortoise_state = start;
+    let mut hare_state = next(&tortoise_state);
+    for _ in 1..lambda {
+        hare_state = next(&hare_state);
+    }
+
+    let mut mu = 0;
+    while hash(&tortoise_state) != hash(&hare_state) {
+        tortoise_state = next(&tortoise_state);
+        hare_state = next(&hare_state);
+        mu += 1;
+    }
+
+    Cycle { mu, lambda }
+}
+
+fn hash<T: Hash>(state: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pure_cycle() {
+        let cycle = find_cycle(0, |n| (n + 1) % 7);
+
+        assert_eq!(Cycle { mu: 0, lambda: 7 }, cycle);
+    }
+
+    #[test]
+    fn prefix_and_period() {
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 6 -> 3 ...
+        let cycle = find_cycle(0, |&n| if n == 6 { 3 } else { n + 1 });
+
+        assert_eq!(Cycle { mu: 3, lambda: 4 }, cycle);
+        assert_eq!(2, cycle.equivalent_steps(2));
+        assert_eq!(3, cycle.equivalent_steps(7));
+        assert_eq!(5, cycle.equivalent_steps(1_000_000_001));
+    }
+
+    #[test]
+    fn fixed_point() {
+        let cycle = find_cycle(100, |&n: &u32| n.saturating_sub(30));
+
+        assert_eq!(Cycle { mu: 4, lambda: 1 }, cycle);
+        assert_eq!(4, cycle.equivalent_steps(1000));
+    }
+
+    #[test]
+    fn structured_states() {
+        // x^2 + 1 mod 255 starting at 3 enters a cycle after a few steps
+        let sequence = |start: u64, steps: usize| (0..steps).fold(start, |x, _| (x * x + 1) % 255);
+        let cycle = find_cycle(vec![3_u64], |v| vec![(v[0] * v[0] + 1) % 255]);
+
+        assert_eq!(sequence(3, cycle.mu), sequence(3, cycle.mu + cycle.lambda));
+        assert_ne!(
+            sequence(3, cycle.mu.saturating_sub(1)),
+            sequence(3, cycle.mu + cycle.lambda - 1)
+        );
+        assert_eq!(
+            sequence(3, 12345),
+            sequence(3, cycle.equivalent_steps(12345))
+        );
+    }
+}
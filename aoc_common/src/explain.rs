@@ -0,0 +1,75 @@
+use std::fmt;
+
+// Something a solution found on its way to the answer, like the digits of a calibration line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub topic: &'static str,
+    pub detail: String,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.topic, self.detail)
+    }
+}
+
+// Where solutions report their steps, instead of `println!`s added by hand when an answer is
+// wrong. The details are only built when explaining is on, so days can report freely
+#[derive(Debug, Default)]
+pub struct Explain {
+    steps: Option<Vec<Step>>,
+}
+
+impl Explain {
+    pub fn new(on: bool) -> Self {
+        Self {
+            steps: on.then(Vec::new),
+        }
+    }
+
+    pub fn off() -> Self {
+        Self::new(false)
+    }
+
+    pub fn on() -> Self {
+        Self::new(true)
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.steps.is_some()
+    }
+
+    pub fn step(&mut self, topic: &'static str, detail: impl FnOnce() -> String) {
+        if let Some(steps) = &mut self.steps {
+            steps.push(Step {
+                topic,
+                detail: detail(),
+            });
+        }
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        self.steps.as_deref().unwrap_or_default()
+    }
+
+    pub fn into_steps(self) -> Vec<Step> {
+        self.steps.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_records_when_on() {
+        let mut explain = Explain::off();
+        explain.step("line", || unreachable!());
+        assert!(explain.steps().is_empty());
+
+        let mut explain = Explain::on();
+        explain.step("line", || String::from("a1b2 -> 12"));
+        assert_eq!("line: a1b2 -> 12", explain.steps()[0].to_string());
+        assert_eq!(1, explain.into_steps().len());
+    }
+}
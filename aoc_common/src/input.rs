@@ -0,0 +1,81 @@
+// Every puzzle input goes through here, so files saved with "\r\n" endings, a BOM or trailing
+// spaces parse the same as the originals. Lines are trimmed on both sides: indentation is never
+// meaningful in the puzzles, and the inline examples in tests are indented.
+
+const BOM: char = '\u{feff}';
+
+pub fn clean_line(line: &str) -> &str {
+    line.trim_start_matches(BOM).trim()
+}
+
+// Trimmed lines, without the blank lines at the start and at the end
+pub fn lines(input: &str) -> impl Iterator<Item = &str> + '_ {
+    let lines = input.split('\n').map(clean_line).collect::<Vec<_>>();
+
+    let start = lines
+        .iter()
+        .position(|line| !line.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(start, |end| end + 1);
+
+    lines.into_iter().skip(start).take(end - start)
+}
+
+// Groups of lines separated by one or more blank lines
+pub fn sections(input: &str) -> Vec<Vec<&str>> {
+    let mut sections = vec![Vec::new()];
+
+    for line in lines(input) {
+        if !line.is_empty() {
+            sections.last_mut().unwrap().push(line);
+        } else if !sections.last().unwrap().is_empty() {
+            sections.push(Vec::new());
+        }
+    }
+
+    sections.retain(|section| !section.is_empty());
+    sections
+}
+
+// The cleaned input as a single string with "\n" endings
+pub fn normalize(input: &str) -> String {
+    lines(input).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleans_lines() {
+        let input = "\u{feff}\r\n  abc \r\n\tdef\r\n\r\n  \r\nghi  \r\n\r\n";
+
+        assert_eq!(
+            vec!["abc", "def", "", "", "ghi"],
+            lines(input).collect::<Vec<_>>()
+        );
+        assert_eq!("abc\ndef\n\n\nghi", normalize(input));
+        assert_eq!("abc", clean_line("\u{feff}abc\r"));
+    }
+
+    #[test]
+    fn splits_sections() {
+        let input = "a\nb\n\nc\n \n\r\nd\ne\n";
+
+        assert_eq!(
+            vec![vec!["a", "b"], vec!["c"], vec!["d", "e"]],
+            sections(input)
+        );
+        assert_eq!(sections(input), sections(&input.replace('\n', "\r\n")));
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert_eq!(0, lines("").count());
+        assert_eq!(0, lines(" \r\n\n").count());
+        assert!(sections("\n\n").is_empty());
+    }
+}
@@ -0,0 +1,518 @@
+use std::env;
+use std::fmt::{self, Display};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "fetch")]
+use std::time::Duration;
+
+pub const BASE_URL: &str = "https://adventofcode.com";
+pub const SESSION_VAR: &str = "AOC_SESSION";
+
+// The site asks automated tools to identify themselves
+#[cfg(feature = "fetch")]
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    TooHigh,
+    TooLow,
+    // Wrong, without a hint on which side
+    Incorrect,
+    // Rate limited, nothing was judged
+    TooRecent,
+    // The part was already solved or isn't unlocked yet
+    WrongLevel,
+}
+
+impl Verdict {
+    // Only these say something about the answer and are worth remembering
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Verdict::Correct | Verdict::TooHigh | Verdict::TooLow | Verdict::Incorrect
+        )
+    }
+
+    // Reads the verdict out of the page returned after posting an answer
+    pub fn from_response(html: &str) -> Option<Self> {
+        if html.contains("That's the right answer") {
+            Some(Verdict::Correct)
+        } else if html.contains("That's not the right answer") {
+            if html.contains("your answer is too high") {
+                Some(Verdict::TooHigh)
+            } else if html.contains("your answer is too low") {
+                Some(Verdict::TooLow)
+            } else {
+                Some(Verdict::Incorrect)
+            }
+        } else if html.contains("You gave an answer too recently") {
+            Some(Verdict::TooRecent)
+        } else if html.contains("You don't seem to be solving the right level") {
+            Some(Verdict::WrongLevel)
+        } else {
+            None
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Verdict::Correct => "correct",
+            Verdict::TooHigh => "too-high",
+            Verdict::TooLow => "too-low",
+            Verdict::Incorrect => "incorrect",
+            Verdict::TooRecent => "too-recent",
+            Verdict::WrongLevel => "wrong-level",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            Verdict::Correct,
+            Verdict::TooHigh,
+            Verdict::TooLow,
+            Verdict::Incorrect,
+        ]
+        .into_iter()
+        .find(|verdict| verdict.name() == name)
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Where inputs come from and answers go to. Tests swap the site for a local fake
+pub trait PuzzleSource {
+    fn fetch_input(&self, year: u16, day: u8) -> io::Result<String>;
+
+    fn submit(&self, year: u16, day: u8, part: u8, answer: &str) -> io::Result<Verdict>;
+}
+
+#[cfg(feature = "fetch")]
+pub struct HttpSource {
+    base_url: String,
+    session: String,
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "fetch")]
+impl HttpSource {
+    pub fn new(base_url: &str, session: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            session: session.trim().to_owned(),
+            agent: ureq::AgentBuilder::new()
+                .user_agent(USER_AGENT)
+                .timeout(Duration::from_secs(30))
+                .build(),
+        }
+    }
+
+    // The session token is the `session` cookie of a logged in browser
+    pub fn from_env() -> io::Result<Self> {
+        let session = env::var(SESSION_VAR).map_err(|_| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{SESSION_VAR} is not set"))
+        })?;
+
+        Ok(Self::new(BASE_URL, &session))
+    }
+
+    fn day_url(&self, year: u16, day: u8) -> String {
+        format!("{}/{year}/day/{day}", self.base_url)
+    }
+
+    fn cookie(&self) -> String {
+        format!("session={}", self.session)
+    }
+}
+
+#[cfg(feature = "fetch")]
+impl PuzzleSource for HttpSource {
+    fn fetch_input(&self, year: u16, day: u8) -> io::Result<String> {
+        self.agent
+            .get(&format!("{}/input", self.day_url(year, day)))
+            .set("Cookie", &self.cookie())
+            .call()
+            .map_err(io::Error::other)?
+            .into_string()
+    }
+
+    fn submit(&self, year: u16, day: u8, part: u8, answer: &str) -> io::Result<Verdict> {
+        let html = self
+            .agent
+            .post(&format!("{}/answer", self.day_url(year, day)))
+            .set("Cookie", &self.cookie())
+            .send_form(&[("level", &part.to_string()), ("answer", answer)])
+            .map_err(io::Error::other)?
+            .into_string()?;
+
+        Verdict::from_response(&html)
+            .ok_or_else(|| io::Error::other("Unrecognized response to the submission"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submission {
+    pub day: u8,
+    pub part: u8,
+    pub answer: String,
+    pub verdict: Verdict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub verdict: Verdict,
+    // False when the verdict was known from earlier submissions
+    pub sent: bool,
+}
+
+// Inputs cached as `<dir>/<year>/day_XX.txt`, next to a `submissions.tsv` log of every judged
+// answer, so nothing is downloaded twice and no answer already known to be wrong is sent again
+pub struct Inputs<S: PuzzleSource> {
+    dir: PathBuf,
+    source: S,
+}
+
+impl<S: PuzzleSource> Inputs<S> {
+    pub fn new(dir: impl Into<PathBuf>, source: S) -> Self {
+        Self {
+            dir: dir.into(),
+            source,
+        }
+    }
+
+    pub fn path(&self, year: u16, day: u8) -> PathBuf {
+        self.year_dir(year).join(format!("day_{day:02}.txt"))
+    }
+
+    fn year_dir(&self, year: u16) -> PathBuf {
+        self.dir.join(year.to_string())
+    }
+
+    fn log_path(&self, year: u16) -> PathBuf {
+        self.year_dir(year).join("submissions.tsv")
+    }
+
+    // The cached input, downloaded first on a miss
+    pub fn input(&self, year: u16, day: u8) -> io::Result<String> {
+        let path = self.path(year, day);
+
+        if path.exists() {
+            return fs::read_to_string(path);
+        }
+
+        let input = self.source.fetch_input(year, day)?;
+        fs::create_dir_all(self.year_dir(year))?;
+        fs::write(path, &input)?;
+
+        Ok(input)
+    }
+
+    pub fn submissions(&self, year: u16) -> io::Result<Vec<Submission>> {
+        let path = self.log_path(year);
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(parse_log(&fs::read_to_string(path)?))
+    }
+
+    pub fn submit(&self, year: u16, day: u8, part: u8, answer: &str) -> io::Result<Outcome> {
+        let answer = answer.trim();
+        let previous = self
+            .submissions(year)?
+            .into_iter()
+            .filter(|s| s.day == day && s.part == part)
+            .collect::<Vec<_>>();
+
+        if let Some(verdict) = known_verdict(&previous, answer) {
+            return Ok(Outcome {
+                verdict,
+                sent: false,
+            });
+        }
+
+        let verdict = self.source.submit(year, day, part, answer)?;
+
+        if verdict.is_final() {
+            fs::create_dir_all(self.year_dir(year))?;
+            let mut log = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.log_path(year))?;
+            writeln!(log, "{day}\t{part}\t{verdict}\t{answer}")?;
+        }
+
+        Ok(Outcome {
+            verdict,
+            sent: true,
+        })
+    }
+}
+
+fn parse_log(log: &str) -> Vec<Submission> {
+    log.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let day = fields.next()?.parse().ok()?;
+            let part = fields.next()?.parse().ok()?;
+            let verdict = Verdict::from_name(fields.next()?)?;
+            let answer = fields.next()?.to_owned();
+
+            Some(Submission {
+                day,
+                part,
+                answer,
+                verdict,
+            })
+        })
+        .collect()
+}
+
+// What earlier verdicts for the same part already say about an answer. Besides repeats, a known
+// correct answer settles everything and numeric answers are checked against the known bounds
+fn known_verdict(previous: &[Submission], answer: &str) -> Option<Verdict> {
+    if let Some(submission) = previous.iter().find(|s| s.answer == answer) {
+        return Some(submission.verdict);
+    }
+
+    if previous.iter().any(|s| s.verdict == Verdict::Correct) {
+        return Some(Verdict::Incorrect);
+    }
+
+    let value = answer.parse::<i128>().ok()?;
+    let bound = |verdict| {
+        previous
+            .iter()
+            .filter(move |s| s.verdict == verdict)
+            .filter_map(|s| s.answer.parse::<i128>().ok())
+    };
+
+    if bound(Verdict::TooHigh).any(|high| value >= high) {
+        Some(Verdict::TooHigh)
+    } else if bound(Verdict::TooLow).any(|low| value <= low) {
+        Some(Verdict::TooLow)
+    } else {
+        None
+    }
+}
+
+// `AOC_CACHE_DIR`, or a `cache` directory at the root of the workspace
+pub fn default_cache_dir() -> PathBuf {
+    env::var_os("AOC_CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .parent()
+                .unwrap()
+                .join("cache")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdicts_from_responses() {
+        let verdict = |text| Verdict::from_response(text);
+
+        assert_eq!(
+            Some(Verdict::Correct),
+            verdict("<p>That's the right answer! You are one gold star closer.</p>")
+        );
+        assert_eq!(
+            Some(Verdict::TooLow),
+            verdict("That's not the right answer; your answer is too low.")
+        );
+        assert_eq!(
+            Some(Verdict::Incorrect),
+            verdict("That's not the right answer. If you're stuck, ...")
+        );
+        assert_eq!(
+            Some(Verdict::TooRecent),
+            verdict("You gave an answer too recently; you have to wait")
+        );
+        assert_eq!(None, verdict("<html></html>"));
+        assert!(!Verdict::TooRecent.is_final());
+    }
+
+    // Against a fake of the site, which needs the HTTP source
+    #[cfg(feature = "fetch")]
+    mod http {
+        use std::collections::HashMap;
+        use std::io::{BufRead, BufReader, Read};
+        use std::net::TcpListener;
+        use std::sync::{Arc, Mutex};
+        use std::thread;
+
+        use super::super::*;
+
+        const SESSION: &str = "secret";
+
+        // Serves the same endpoints as the site: inputs for a valid session and verdicts for answers
+        struct FakeServer {
+            url: String,
+            requests: Arc<Mutex<Vec<String>>>,
+        }
+
+        impl FakeServer {
+            fn start(answers: HashMap<(u8, u8), i64>) -> Self {
+                let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+                let url = format!("http://{}", listener.local_addr().unwrap());
+                let requests = Arc::new(Mutex::new(Vec::new()));
+                let log = Arc::clone(&requests);
+
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        let mut stream = stream.unwrap();
+                        let mut reader = BufReader::new(&stream);
+
+                        let mut request_line = String::new();
+                        reader.read_line(&mut request_line).unwrap();
+                        let mut headers = HashMap::new();
+                        loop {
+                            let mut line = String::new();
+                            reader.read_line(&mut line).unwrap();
+                            if line.trim().is_empty() {
+                                break;
+                            }
+                            let (name, value) = line.split_once(':').unwrap();
+                            headers.insert(name.to_lowercase(), value.trim().to_owned());
+                        }
+                        let length = headers
+                            .get("content-length")
+                            .map_or(0, |l| l.parse().unwrap());
+                        let mut body = vec![0; length];
+                        reader.read_exact(&mut body).unwrap();
+                        let body = String::from_utf8(body).unwrap();
+
+                        let path = request_line.split_whitespace().nth(1).unwrap().to_owned();
+                        log.lock()
+                            .unwrap()
+                            .push(format!("{path} {body}").trim().to_owned());
+
+                        let (status, response) =
+                            if headers.get("cookie") != Some(&format!("session={SESSION}")) {
+                                ("400 Bad Request", "Puzzle inputs differ by user".to_owned())
+                            } else {
+                                respond(&path, &body, &answers)
+                            };
+
+                        write!(
+                            stream,
+                            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                            response.len()
+                        )
+                        .unwrap();
+                    }
+                });
+
+                Self { url, requests }
+            }
+
+            fn requests(&self) -> Vec<String> {
+                self.requests.lock().unwrap().clone()
+            }
+        }
+
+        fn respond(
+            path: &str,
+            body: &str,
+            answers: &HashMap<(u8, u8), i64>,
+        ) -> (&'static str, String) {
+            let parts = path.split('/').collect::<Vec<_>>();
+            let day = parts[3].parse::<u8>().unwrap();
+
+            match parts[4] {
+                "input" => ("200 OK", format!("input of {} day {day}\n", parts[1])),
+                "answer" => {
+                    let form = body
+                        .split('&')
+                        .filter_map(|pair| pair.split_once('='))
+                        .collect::<HashMap<_, _>>();
+                    let part = form["level"].parse().unwrap();
+                    let answer = form["answer"].parse::<i64>().unwrap();
+
+                    let text = match answer.cmp(&answers[&(day, part)]) {
+                        std::cmp::Ordering::Equal => "That's the right answer!".to_owned(),
+                        order => format!(
+                            "That's not the right answer; your answer is too {}.",
+                            if order.is_gt() { "high" } else { "low" }
+                        ),
+                    };
+
+                    ("200 OK", format!("<article><p>{text}</p></article>"))
+                }
+                _ => ("404 Not Found", String::new()),
+            }
+        }
+
+        fn temp_dir(name: &str) -> PathBuf {
+            let dir = env::temp_dir().join(format!("{name}_{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            dir
+        }
+
+        #[test]
+        fn caches_inputs() {
+            let server = FakeServer::start(HashMap::new());
+            let dir = temp_dir("caches_inputs");
+            let inputs = Inputs::new(&dir, HttpSource::new(&server.url, SESSION));
+
+            assert_eq!("input of 2023 day 5\n", inputs.input(2023, 5).unwrap());
+            assert_eq!("input of 2023 day 5\n", inputs.input(2023, 5).unwrap());
+            assert_eq!(
+                "input of 2023 day 5\n",
+                fs::read_to_string(dir.join("2023/day_05.txt")).unwrap()
+            );
+            assert_eq!(vec!["/2023/day/5/input"], server.requests());
+
+            let unauthorized = Inputs::new(&dir, HttpSource::new(&server.url, "wrong"));
+            assert!(unauthorized.input(2023, 6).is_err());
+            assert!(!unauthorized.path(2023, 6).exists());
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn never_resends_answers() {
+            let server = FakeServer::start(HashMap::from([((1, 1), 100), ((1, 2), 7)]));
+            let dir = temp_dir("never_resends_answers");
+            let inputs = Inputs::new(&dir, HttpSource::new(&server.url, SESSION));
+
+            let submit = |part, answer| {
+                let outcome = inputs.submit(2023, 1, part, answer).unwrap();
+                (outcome.verdict, outcome.sent)
+            };
+
+            assert_eq!((Verdict::TooHigh, true), submit(1, "150"));
+            assert_eq!((Verdict::TooHigh, false), submit(1, "150"));
+            assert_eq!((Verdict::TooHigh, false), submit(1, "200"));
+            assert_eq!((Verdict::TooLow, true), submit(1, "50"));
+            assert_eq!((Verdict::TooLow, false), submit(1, "49"));
+            assert_eq!((Verdict::Correct, true), submit(1, "100"));
+            assert_eq!((Verdict::Correct, false), submit(1, "100"));
+            assert_eq!((Verdict::Incorrect, false), submit(1, "101"));
+            assert_eq!((Verdict::TooLow, true), submit(2, "6"));
+
+            assert_eq!(
+                vec![
+                    "/2023/day/1/answer level=1&answer=150",
+                    "/2023/day/1/answer level=1&answer=50",
+                    "/2023/day/1/answer level=1&answer=100",
+                    "/2023/day/1/answer level=2&answer=6",
+                ],
+                server.requests()
+            );
+            assert_eq!(4, inputs.submissions(2023).unwrap().len());
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
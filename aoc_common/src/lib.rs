@@ -1,3 +1,17 @@
+#[macro_use]
+extern crate lazy_static;
+
+pub mod bit_grid;
+pub mod check;
+pub mod cycle;
+pub mod explain;
+pub mod input;
+pub mod inputs;
+pub mod page;
+pub mod parse;
+pub mod solution;
+pub mod stream;
+
 use std::cmp::Ordering;
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
@@ -108,7 +122,7 @@ mod tests {
     fn test_shoelace() {
         assert_eq!(
             16.5,
-            Point::shoelace_area(&vec![
+            Point::shoelace_area(&[
                 Point::new(1, 6),
                 Point::new(3, 1),
                 Point::new(7, 2),
@@ -0,0 +1,298 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use regex::Regex;
+
+use super::check::{Outcome, Status};
+use super::explain::Explain;
+use super::solution::Entry;
+
+lazy_static! {
+    static ref ARTICLE_REGEX: Regex = Regex::new(r"(?s)<article[^>]*>(.*?)</article>").unwrap();
+    static ref EXAMPLE_REGEX: Regex = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+    static ref ANSWER_REGEX: Regex =
+        Regex::new(r"(?s)<code><em>(.*?)</em></code>|<em><code>(.*?)</code></em>").unwrap();
+    static ref TAG_REGEX: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+// What one part of the description says: the example blocks it shows and the emphasised answer.
+// `example` is the block the answer is about, the last one before it, or the one from the
+// previous part when this part only reuses it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PagePart {
+    pub examples: Vec<String>,
+    pub example: Option<String>,
+    pub answer: Option<String>,
+}
+
+// A puzzle description as saved from the browser, one `<article>` per unlocked part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PuzzlePage {
+    pub parts: Vec<PagePart>,
+}
+
+impl PuzzlePage {
+    pub fn load(path: &Path) -> Self {
+        let html = fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("Could not read {}: {e}", path.display()));
+
+        Self::parse(&html)
+    }
+
+    pub fn parse(html: &str) -> Self {
+        let mut articles = ARTICLE_REGEX
+            .captures_iter(html)
+            .map(|caps| caps.get(1).unwrap().as_str())
+            .collect::<Vec<_>>();
+        if articles.is_empty() {
+            articles.push(html);
+        }
+
+        let mut parts: Vec<PagePart> = Vec::new();
+
+        for article in articles {
+            let examples = EXAMPLE_REGEX
+                .captures_iter(article)
+                .map(|caps| {
+                    let block = caps.get(1).unwrap();
+                    (block.start(), text(block.as_str()))
+                })
+                .collect::<Vec<_>>();
+
+            let answer = ANSWER_REGEX.captures_iter(article).last().map(|caps| {
+                let answer = caps.get(1).or(caps.get(2)).unwrap();
+                (answer.start(), text(answer.as_str()))
+            });
+
+            let answer_start = answer.as_ref().map_or(article.len(), |(start, _)| *start);
+            let example = examples
+                .iter()
+                .rev()
+                .find(|(start, _)| *start < answer_start)
+                .map(|(_, example)| example.clone())
+                .or_else(|| parts.last().and_then(|part| part.example.clone()));
+
+            parts.push(PagePart {
+                examples: examples.into_iter().map(|(_, example)| example).collect(),
+                example,
+                answer: answer.map(|(_, answer)| answer),
+            });
+        }
+
+        Self { parts }
+    }
+
+    // 1 based, like the parts of a solution
+    pub fn part(&self, part: u8) -> Option<&PagePart> {
+        self.parts.get(usize::from(part).checked_sub(1)?)
+    }
+}
+
+// The text inside an element, without markup such as the `<em>` highlighting cells in examples
+fn text(html: &str) -> String {
+    TAG_REGEX
+        .replace_all(html, "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+// Runs the solution on each part's example. Days whose examples use different parameters than
+// the real input (fewer steps, a smaller expansion) are expected to fail here
+pub fn check_examples(entry: &Entry, page: &PuzzlePage, explain: bool) -> Vec<Outcome> {
+    (1..)
+        .zip(&page.parts)
+        .filter_map(|(part, page_part)| {
+            let example = page_part.example.as_ref()?;
+
+            let mut explain = Explain::new(explain);
+            let start = Instant::now();
+            let answer = entry.run(example, part, &mut explain);
+            let elapsed = start.elapsed();
+
+            let status = match (&answer, &page_part.answer) {
+                (Err(error), _) => Status::Invalid {
+                    error: error.to_string(),
+                },
+                (Ok(_), None) => Status::Missing,
+                (Ok(answer), Some(expected)) if Some(expected) == answer.as_ref() => Status::Pass,
+                (Ok(_), Some(expected)) => Status::Fail {
+                    expected: expected.clone(),
+                },
+            };
+
+            Some(Outcome {
+                puzzle: entry.puzzle,
+                part,
+                answer: answer.ok().flatten(),
+                status,
+                elapsed,
+                steps: explain.into_steps(),
+            })
+        })
+        .collect()
+}
+
+// A test to paste in the day's module, written like the hand copied ones
+pub fn generate_test(solution: &str, page: &PuzzlePage) -> String {
+    let mut test = String::from(
+        "    #[test]\n    fn page_examples() {\n        use crate::common::{explain::Explain, solution::Solution};\n",
+    );
+
+    for (part, page_part) in (1..).zip(&page.parts) {
+        let (Some(example), Some(answer)) = (&page_part.example, &page_part.answer) else {
+            continue;
+        };
+
+        let mut literal = String::from("\"\n");
+        for line in example.lines() {
+            literal += &format!(
+                "            {}\n",
+                line.replace('\\', "\\\\").replace('"', "\\\"")
+            );
+        }
+        literal += "        \"";
+
+        let result = match part {
+            1 => format!(
+                "{solution}::part_one(&{solution}::parse(example).unwrap(), &mut Explain::off())"
+            ),
+            _ => format!(
+                "{solution}::part_two(&{solution}::parse(example).unwrap(), &mut Explain::off())"
+            ),
+        };
+        let expected = match part {
+            1 => format!("{answer:?}"),
+            _ => format!("Some({answer:?}.to_string())"),
+        };
+
+        test += &format!("\n        let example = {literal};\n");
+        test += &format!("        assert_eq!({expected}, {result});\n");
+    }
+
+    test + "    }\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse::ParseResult;
+    use super::super::solution::{Puzzle, Solution};
+    use super::*;
+
+    // Trimmed down from a saved day 01 page, keeping the markup around examples and answers
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en-us">
+<body>
+<main>
+<article class="day-desc"><h2>--- Day 1: Trebuchet?! ---</h2>
+<p>For example:</p>
+<pre><code>1abc2
+pqr3stu8vwx
+a1b2c3d4e5f
+treb7uchet
+</code></pre>
+<p>In this example, the calibration values of these four lines are <code>12</code>, <code>38</code>, <code>15</code>, and <code>77</code>. Adding these together produces <code><em>142</em></code>.</p>
+<p>Consider your <em>entire</em> calibration document.</p>
+</article>
+<p>Your puzzle answer was <code>54916</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<p>Equipped with this new information, you now need to find the real first and last digit on each line. For example:</p>
+<pre><code>two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqr<em>st</em>sixteen
+</code></pre>
+<p>Adding these together produces <code><em>281</em></code>.</p>
+</article>
+</main>
+</body>
+</html>"#;
+
+    struct Digits;
+
+    impl Solution for Digits {
+        type Parsed<'a> = Vec<&'a str>;
+
+        fn parse(input: &str) -> ParseResult<Self::Parsed<'_>> {
+            Ok(input.lines().collect())
+        }
+
+        fn part_one(parsed: &Self::Parsed<'_>, _: &mut Explain) -> String {
+            parsed.len().to_string()
+        }
+
+        fn part_two(_parsed: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+            Some("281".to_string())
+        }
+    }
+
+    #[test]
+    fn parse_page() {
+        let page = PuzzlePage::parse(PAGE);
+
+        assert_eq!(2, page.parts.len());
+
+        let one = page.part(1).unwrap();
+        assert_eq!(
+            Some("1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\n"),
+            one.example.as_deref()
+        );
+        assert_eq!(Some("142"), one.answer.as_deref());
+
+        let two = page.part(2).unwrap();
+        assert_eq!(1, two.examples.len());
+        assert!(two.example.as_ref().unwrap().ends_with("7pqrstsixteen\n"));
+        assert_eq!(Some("281"), two.answer.as_deref());
+        assert!(page.part(3).is_none());
+    }
+
+    #[test]
+    fn reused_example_and_entities() {
+        let page = PuzzlePage::parse(
+            "<article><pre><code>a -&gt; b\n</code></pre><p><code><em>7</em></code></p></article>\
+             <article><p>Now it is <em><code>9</code></em>.</p></article>",
+        );
+
+        assert_eq!(Some("a -> b\n"), page.parts[0].example.as_deref());
+        assert_eq!(page.parts[0].example, page.parts[1].example);
+        assert!(page.parts[1].examples.is_empty());
+        assert_eq!(Some("9"), page.parts[1].answer.as_deref());
+    }
+
+    #[test]
+    fn check_and_generate() {
+        let page = PuzzlePage::parse(PAGE);
+        let entry = Entry::new::<Digits>(Puzzle {
+            year: 2023,
+            day: 1,
+            res_dir: "res",
+        });
+
+        let statuses = check_examples(&entry, &page, false)
+            .into_iter()
+            .map(|outcome| outcome.status)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Status::Fail {
+                    expected: "142".to_string()
+                },
+                Status::Pass
+            ],
+            statuses
+        );
+
+        let test = generate_test("Day01", &page);
+        assert!(test.starts_with("    #[test]\n    fn page_examples() {\n"));
+        assert!(test.contains("Solution};\n\n        let example = \"\n            1abc2\n"));
+        assert!(test.contains("        assert_eq!(\"142\", Day01::part_one(&Day01::parse(example).unwrap(), &mut Explain::off()));\n"));
+        assert!(test.contains("        assert_eq!(Some(\"281\".to_string()), Day01::part_two(&Day01::parse(example).unwrap(), &mut Explain::off()));\n"));
+        assert!(test.ends_with("\n    }\n"));
+    }
+}
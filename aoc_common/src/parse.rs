@@ -0,0 +1,101 @@
+use std::{error::Error, fmt, io, str::FromStr};
+
+// Why an input couldn't be parsed. Parsers return it instead of panicking, so a malformed or
+// truncated input is reported and fuzzing only finds real bugs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+impl ParseError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+// Lets the buffered solutions report bad lines like any other read error
+impl From<ParseError> for io::Error {
+    fn from(error: ParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+pub fn number<T: FromStr>(text: &str) -> ParseResult<T> {
+    text.parse()
+        .map_err(|_| ParseError::new(format!("Invalid number {text:?}")))
+}
+
+// Numbers separated by whitespace
+pub fn numbers<T: FromStr>(text: &str) -> ParseResult<Vec<T>> {
+    text.split_whitespace().map(number).collect()
+}
+
+// For the pieces an input must have, like `parts.next().required("the targets")?`
+pub trait Required<T> {
+    fn required(self, what: &str) -> ParseResult<T>;
+}
+
+impl<T> Required<T> for Option<T> {
+    fn required(self, what: &str) -> ParseResult<T> {
+        self.ok_or_else(|| ParseError::new(format!("Missing {what}")))
+    }
+}
+
+// Rows of cells with the same length, each char read by `cell`
+pub fn grid<T>(input: &str, cell: impl Fn(char) -> ParseResult<T>) -> ParseResult<Vec<Vec<T>>> {
+    let rows = super::input::lines(input)
+        .map(|line| line.chars().map(&cell).collect::<ParseResult<Vec<_>>>())
+        .collect::<ParseResult<Vec<_>>>()?;
+
+    match rows.first() {
+        None => Err(ParseError::new("Empty grid")),
+        Some(first) if first.is_empty() || rows.iter().any(|row| row.len() != first.len()) => {
+            Err(ParseError::new("Rows of different lengths"))
+        }
+        Some(_) => Ok(rows),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_bad_pieces() {
+        assert_eq!(Ok(12), number::<u8>("12"));
+        assert_eq!(
+            Err(ParseError::new("Invalid number \"300\"")),
+            number::<u8>("300")
+        );
+        assert_eq!(Ok(vec![1, -2]), numbers::<i8>(" 1  -2 "));
+        assert_eq!(
+            "Missing the id",
+            None::<u8>.required("the id").unwrap_err().to_string()
+        );
+
+        let error = io::Error::from(ParseError::new("Bad line"));
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn grids_are_rectangular() {
+        let digit = |c: char| c.to_digit(10).required("a digit");
+
+        assert_eq!(Ok(vec![vec![1, 2], vec![3, 4]]), grid("12\n34", digit));
+        assert!(grid("12\n3", digit).is_err());
+        assert!(grid("12\n3x", digit).is_err());
+        assert!(grid("\n\n", digit).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+use super::explain::Explain;
+use super::parse::ParseResult;
+
+// A day split in its parsing and solving steps, so both can be run and timed on their own
+pub trait Solution {
+    type Parsed<'a>;
+
+    // Malformed inputs are errors rather than panics, the parts can then trust what they get
+    fn parse(input: &str) -> ParseResult<Self::Parsed<'_>>;
+
+    // The steps worth seeing when an answer is wrong go to `explain`
+    fn part_one(parsed: &Self::Parsed<'_>, explain: &mut Explain) -> String;
+
+    // Days without a second part keep the default
+    fn part_two(_parsed: &Self::Parsed<'_>, _explain: &mut Explain) -> Option<String> {
+        None
+    }
+}
+
+// For answers worked out with checked arithmetic, which are None when they don't fit
+pub fn or_overflow(answer: Option<impl ToString>) -> String {
+    answer.map_or_else(|| String::from("overflow"), |answer| answer.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Puzzle {
+    pub year: u16,
+    pub day: u8,
+    // Directory holding the `day_XX.txt` inputs and `day_XX.html` pages
+    pub res_dir: &'static str,
+}
+
+impl Puzzle {
+    pub fn input_path(&self) -> PathBuf {
+        Path::new(self.res_dir).join(format!("day_{:02}.txt", self.day))
+    }
+
+    // The description page saved from the browser, holding the examples
+    pub fn page_path(&self) -> PathBuf {
+        Path::new(self.res_dir).join(format!("day_{:02}.html", self.day))
+    }
+}
+
+// Lets every crate list its days once and have them benchmarked, checked or run
+pub trait Visitor {
+    fn visit<S: Solution>(&mut self, puzzle: Puzzle);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub puzzle: Puzzle,
+    run: fn(&str, u8, &mut Explain) -> ParseResult<Option<String>>,
+}
+
+impl Entry {
+    pub fn new<S: Solution>(puzzle: Puzzle) -> Self {
+        Self {
+            puzzle,
+            run: run::<S>,
+        }
+    }
+
+    // None when the day has no such part
+    pub fn run(&self, input: &str, part: u8, explain: &mut Explain) -> ParseResult<Option<String>> {
+        (self.run)(input, part, explain)
+    }
+}
+
+fn run<S: Solution>(input: &str, part: u8, explain: &mut Explain) -> ParseResult<Option<String>> {
+    let parsed = S::parse(input)?;
+
+    Ok(match part {
+        1 => Some(S::part_one(&parsed, explain)),
+        2 => S::part_two(&parsed, explain),
+        _ => None,
+    })
+}
+
+#[derive(Debug, Default)]
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn get(&self, year: u16, day: u8) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.puzzle.year == year && entry.puzzle.day == day)
+    }
+}
+
+impl Visitor for Registry {
+    fn visit<S: Solution>(&mut self, puzzle: Puzzle) {
+        self.entries.push(Entry::new::<S>(puzzle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::number;
+
+    struct Sum;
+
+    impl Solution for Sum {
+        type Parsed<'a> = Vec<usize>;
+
+        fn parse(input: &str) -> ParseResult<Self::Parsed<'_>> {
+            input.split(',').map(number).collect()
+        }
+
+        fn part_one(parsed: &Self::Parsed<'_>, explain: &mut Explain) -> String {
+            explain.step("terms", || format!("{parsed:?}"));
+            parsed.iter().sum::<usize>().to_string()
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = Registry::new();
+        registry.visit::<Sum>(Puzzle {
+            year: 2023,
+            day: 3,
+            res_dir: "res",
+        });
+
+        let entry = registry.get(2023, 3).unwrap();
+
+        let mut explain = Explain::on();
+        assert_eq!(
+            Ok(Some("6".to_string())),
+            entry.run("1,2,3", 1, &mut explain)
+        );
+        assert_eq!("terms: [1, 2, 3]", explain.steps()[0].to_string());
+        assert_eq!(Ok(None), entry.run("1,2,3", 2, &mut Explain::off()));
+        assert!(entry.run("1,x,3", 1, &mut Explain::off()).is_err());
+        assert_eq!(Path::new("res/day_03.txt"), entry.puzzle.input_path());
+        assert!(registry.get(2023, 4).is_none());
+    }
+}
@@ -0,0 +1,106 @@
+use std::io::{self, BufRead};
+
+use super::input::clean_line;
+use super::parse::ParseResult;
+
+// Folds over the lines of a reader reusing a single buffer, so inputs never have to fit in memory.
+// Lines are cleaned the same way as `input::lines` does and blank lines are skipped
+pub fn fold_lines<R, T, F>(reader: R, init: T, mut f: F) -> io::Result<T>
+where
+    R: BufRead,
+    F: FnMut(T, &str) -> T,
+{
+    try_fold_lines(reader, init, |acc, line| Ok(f(acc, line)))
+}
+
+// Like `fold_lines` for lines that may not parse, stopping at the first bad one
+pub fn try_fold_lines<R, T, F>(mut reader: R, init: T, mut f: F) -> io::Result<T>
+where
+    R: BufRead,
+    F: FnMut(T, &str) -> ParseResult<T>,
+{
+    let mut buffer = String::new();
+    let mut acc = init;
+
+    loop {
+        buffer.clear();
+
+        if reader.read_line(&mut buffer)? == 0 {
+            return Ok(acc);
+        }
+
+        let line = clean_line(&buffer);
+        if !line.is_empty() {
+            acc = f(acc, line)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+
+    use super::*;
+    use crate::parse::number;
+
+    // Produces `lines` numbered lines without ever holding them all
+    struct Generated {
+        lines: usize,
+        current: usize,
+        pending: Vec<u8>,
+    }
+
+    impl Read for Generated {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pending.is_empty() && self.current < self.lines {
+                self.pending = format!("{}\r\n", self.current).into_bytes();
+                self.current += 1;
+            }
+
+            let size = self.pending.len().min(buf.len());
+            buf[..size].copy_from_slice(&self.pending[..size]);
+            self.pending.drain(..size);
+
+            Ok(size)
+        }
+    }
+
+    #[test]
+    fn folds_lines() {
+        let lines = fold_lines(
+            "\u{feff}a\nbb \r\n\nccc".as_bytes(),
+            Vec::new(),
+            |mut acc, line| {
+                acc.push(line.to_owned());
+                acc
+            },
+        )
+        .unwrap();
+
+        assert_eq!(vec!["a", "bb", "ccc"], lines);
+    }
+
+    #[test]
+    fn folds_generated_input() {
+        let reader = BufReader::new(Generated {
+            lines: 200_000,
+            current: 0,
+            pending: Vec::new(),
+        });
+
+        let sum = fold_lines(reader, 0, |acc, line| acc + line.parse::<usize>().unwrap()).unwrap();
+
+        assert_eq!(199_999 * 200_000 / 2, sum);
+    }
+
+    #[test]
+    fn stops_at_bad_lines() {
+        let sum = try_fold_lines("1\n2\nx\n3".as_bytes(), 0, |acc, line| {
+            Ok(acc + number::<usize>(line)?)
+        });
+
+        let error = sum.unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert_eq!("Invalid number \"x\"", error.to_string());
+    }
+}
@@ -1,60 +1,294 @@
+use std::io::{self, BufRead};
+
+use crate::common::{
+    explain::Explain, input, parse::ParseResult, solution::Solution, stream::fold_lines,
+};
+
+lazy_static! {
+    static ref DIGITS: Vocabulary = Vocabulary::digits();
+    static ref DIGITS_AND_NAMES: Vocabulary = Vocabulary::english();
+}
+
 pub fn part_one(calibration_text: &str) -> usize {
-    find_calibration_sum(calibration_text, find_numbers)
+    sum_lines(
+        &input::lines(calibration_text).collect::<Vec<_>>(),
+        &DIGITS,
+        &mut Explain::off(),
+    )
 }
 
 pub fn part_two(calibration_text: &str) -> usize {
-    find_calibration_sum(calibration_text, find_numbers_with_names)
+    sum_lines(
+        &input::lines(calibration_text).collect::<Vec<_>>(),
+        &DIGITS_AND_NAMES,
+        &mut Explain::off(),
+    )
+}
+
+pub fn part_one_buffered(reader: impl BufRead) -> io::Result<usize> {
+    fold_lines(reader, 0, |acc, line| {
+        acc + calibration_value(line, &DIGITS)
+    })
+}
+
+pub fn part_two_buffered(reader: impl BufRead) -> io::Result<usize> {
+    fold_lines(reader, 0, |acc, line| {
+        acc + calibration_value(line, &DIGITS_AND_NAMES)
+    })
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    type Parsed<'a> = Vec<&'a str>;
+
+    fn parse(calibration_text: &str) -> ParseResult<Self::Parsed<'_>> {
+        Ok(input::lines(calibration_text).collect())
+    }
+
+    fn part_one(lines: &Self::Parsed<'_>, explain: &mut Explain) -> String {
+        sum_lines(lines, &DIGITS, explain).to_string()
+    }
+
+    fn part_two(lines: &Self::Parsed<'_>, explain: &mut Explain) -> Option<String> {
+        Some(sum_lines(lines, &DIGITS_AND_NAMES, explain).to_string())
+    }
 }
 
-fn find_calibration_sum<NumberFinder>(text: &str, f: NumberFinder) -> usize
-where
-    NumberFinder: Fn(&str) -> Vec<usize>,
-{
-    text.lines()
-        .map(f)
-        .map(combine_to_two_digit)
-        .fold(0, |acc, number| acc + number.unwrap_or(0))
+fn sum_lines(lines: &[&str], vocabulary: &Vocabulary, explain: &mut Explain) -> usize {
+    lines
+        .iter()
+        .map(|line| {
+            let value = calibration_value(line, vocabulary);
+            explain.step("calibration", || {
+                match (vocabulary.first(line), vocabulary.last(line)) {
+                    (Some(first), Some(last)) => {
+                        format!("{line}: first {first}, last {last} -> {value}")
+                    }
+                    _ => format!("{line}: no digit"),
+                }
+            });
+            value
+        })
+        .sum()
 }
 
-fn find_numbers(line: &str) -> Vec<usize> {
-    line.chars()
-        .filter(|c| c.is_numeric())
-        .map(|n| (n as usize) - ('0' as usize))
-        .collect()
+// Lines without any digit are worth nothing
+fn calibration_value(line: &str, vocabulary: &Vocabulary) -> usize {
+    match (vocabulary.first(line), vocabulary.last(line)) {
+        (Some(first), Some(last)) => first * 10 + last,
+        _ => 0,
+    }
 }
 
-const NUMBER_WORDS: [(&str, &str); 9] = [
-    ("one", "o1e"),
-    ("two", "t2o"),
-    ("three", "t3e"),
-    ("four", "f4r"),
-    ("five", "f5e"),
-    ("six", "s6x"),
-    ("seven", "s7n"),
-    ("eight", "e8t"),
-    ("nine", "n9e"),
+const NUMBER_WORDS: [(&str, usize); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
 ];
 
-fn find_numbers_with_names(line: &str) -> Vec<usize> {
-    let mut replaced = line.to_owned();
+// The words standing for numbers, like "7", "seven" or "sieben". Words can overlap in a line, as
+// in "twone", so the first one is looked for from the start and the last one from the end, each
+// in a trie. When words start (or end, for the last one) at the same place, the longest wins, so
+// "twenty one" can be told from "twenty"
+#[derive(Debug)]
+pub struct Vocabulary {
+    forward: Trie,
+    backward: Trie,
+}
+
+impl Vocabulary {
+    pub fn new<'a>(words: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut forward = Trie::new();
+        let mut backward = Trie::new();
+
+        for (word, value) in words {
+            assert!(!word.is_empty(), "Empty word for {value}");
+            forward.insert(word.bytes(), value);
+            backward.insert(word.bytes().rev(), value);
+        }
+
+        Self { forward, backward }
+    }
+
+    // "0" to "9"
+    pub fn digits() -> Self {
+        let digits = (0..10).map(|digit| digit.to_string()).collect::<Vec<_>>();
+
+        Self::new(digits.iter().map(String::as_str).zip(0..))
+    }
+
+    // The digits and the names of 1 to 9
+    pub fn english() -> Self {
+        let digits = (0..10).map(|digit| digit.to_string()).collect::<Vec<_>>();
 
-    for (number, digit) in NUMBER_WORDS {
-        replaced = replaced.replace(number, digit);
+        Self::new(
+            digits
+                .iter()
+                .map(String::as_str)
+                .zip(0..)
+                .chain(NUMBER_WORDS),
+        )
     }
 
-    find_numbers(&replaced)
+    // The value of the word starting first in the line
+    pub fn first(&self, line: &str) -> Option<usize> {
+        let bytes = line.as_bytes();
+
+        (0..bytes.len()).find_map(|start| self.forward.longest(bytes[start..].iter().copied()))
+    }
+
+    // The value of the word ending last in the line
+    pub fn last(&self, line: &str) -> Option<usize> {
+        let bytes = line.as_bytes();
+
+        (0..bytes.len())
+            .rev()
+            .find_map(|end| self.backward.longest(bytes[..=end].iter().rev().copied()))
+    }
+}
+
+#[derive(Debug)]
+struct Trie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: Vec<(u8, usize)>,
+    // Set when a word ends here
+    value: Option<usize>,
 }
 
-fn combine_to_two_digit(numbers: Vec<usize>) -> Option<usize> {
-    let first_and_last = numbers.first().map(|f| (f, numbers.last().unwrap()));
+impl Trie {
+    fn new() -> Self {
+        Self {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+
+    fn child(&self, node: usize, byte: u8) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .find(|&&(child_byte, _)| child_byte == byte)
+            .map(|&(_, child)| child)
+    }
+
+    // A later word replaces the value of an identical one
+    fn insert(&mut self, word: impl Iterator<Item = u8>, value: usize) {
+        let mut node = 0;
+
+        for byte in word {
+            node = match self.child(node, byte) {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.push((byte, child));
+                    child
+                }
+            };
+        }
+
+        self.nodes[node].value = Some(value);
+    }
+
+    // The value of the longest word the bytes start with
+    fn longest(&self, bytes: impl Iterator<Item = u8>) -> Option<usize> {
+        let mut node = 0;
+        let mut longest = None;
 
-    first_and_last.map(|(f, l)| f * 10 + l)
+        for byte in bytes {
+            match self.child(node, byte) {
+                Some(child) => node = child,
+                None => break,
+            }
+            longest = self.nodes[node].value.or(longest);
+        }
+
+        longest
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
+    // Every place a word is found, as (start, end, value)
+    fn naive_matches(vocabulary: &[(&str, usize)], line: &str) -> Vec<(usize, usize, usize)> {
+        vocabulary
+            .iter()
+            .flat_map(|&(word, value)| {
+                line.match_indices(word)
+                    .map(move |(start, _)| (start, start + word.len(), value))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    proptest! {
+        // Letters of the number names, so they often overlap
+        #[test]
+        fn matches_every_place_a_word_is_found(line in "[onetwhrfuivsxg1-9]{0,24}") {
+            let words = (1..10).map(|digit| digit.to_string()).collect::<Vec<_>>();
+            let vocabulary = words
+                .iter()
+                .map(String::as_str)
+                .zip(1..)
+                .chain(NUMBER_WORDS)
+                .collect::<Vec<_>>();
+            let matches = naive_matches(&vocabulary, &line);
+
+            let first = matches.iter().min_by_key(|&&(start, end, _)| (start, usize::MAX - end));
+            let last = matches.iter().max_by_key(|&&(start, end, _)| (end, usize::MAX - start));
+            prop_assert_eq!(first.map(|m| m.2), DIGITS_AND_NAMES.first(&line));
+            prop_assert_eq!(last.map(|m| m.2), DIGITS_AND_NAMES.last(&line));
+        }
+    }
+
+    #[test]
+    fn overlapping_words() {
+        let english = Vocabulary::english();
+        let ends = |line| (english.first(line), english.last(line));
+
+        assert_eq!((Some(1), Some(8)), ends("oneight"));
+        assert_eq!((Some(2), Some(1)), ends("twone"));
+        assert_eq!((Some(8), Some(3)), ends("eighthree"));
+        assert_eq!((Some(7), Some(9)), ends("sevenine"));
+        assert_eq!((Some(2), Some(2)), ends("xtwonex2"));
+        assert_eq!((None, None), ends("otw hre"));
+    }
+
+    #[test]
+    fn other_vocabularies() {
+        let german = Vocabulary::new([("null", 0), ("eins", 1), ("sieben", 7), ("acht", 8)]);
+        assert_eq!(Some(1), german.first("xeinsieben"));
+        assert_eq!(Some(7), german.last("achteinsieben"));
+        assert_eq!(Some(0), german.first("nullacht"));
+
+        // A word much longer than its overlap with the next one
+        let long = Vocabulary::new([("abc", 1), ("bcdefg", 2)]);
+        assert_eq!(
+            (Some(1), Some(2)),
+            (long.first("abcdefg"), long.last("abcdefg"))
+        );
+
+        // The longest word wins when several start or end at the same place
+        let numerals = Vocabulary::new([("twenty", 20), ("twenty one", 21), ("one", 1)]);
+        assert_eq!(Some(21), numerals.first("twenty one"));
+        assert_eq!(Some(21), numerals.last("twenty one"));
+        assert_eq!(Some(20), numerals.first("twenty two"));
+    }
+
     #[test]
     fn simple_digits() {
         let text = "\
@@ -99,6 +333,29 @@ mod tests {
         assert_eq!(33, part_two("threethreetwothree"));
     }
 
+    #[test]
+    fn explained() {
+        let mut explain = Explain::on();
+        Day01::part_two(&Day01::parse("xtwone3four\nnone").unwrap(), &mut explain);
+
+        let steps = explain.steps().iter().map(|step| step.to_string());
+        assert_eq!(
+            vec![
+                "calibration: xtwone3four: first 2, last 4 -> 24",
+                "calibration: none: first 1, last 1 -> 11",
+            ],
+            steps.collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn buffered() {
+        let text = include_str!("../res/day_01.txt");
+
+        assert_eq!(part_one(text), part_one_buffered(text.as_bytes()).unwrap());
+        assert_eq!(part_two(text), part_two_buffered(text.as_bytes()).unwrap());
+    }
+
     #[test]
     fn full_digits_and_numbers() {
         let text = include_str!("../res/day_01.txt");
@@ -1,129 +1,255 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, BufRead},
+};
+
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseError, ParseResult, Required},
+    solution::{or_overflow, Solution},
+    stream::try_fold_lines,
+};
+
 pub fn part_one(games: &str) -> usize {
-    let correct_guess = Guess::new(12, 13, 14);
-    let games = read_file(games);
+    sum_possible_games(&read_file(games).unwrap(), &mut Explain::off())
+}
 
-    find_possible_games(&correct_guess, &games).iter().sum()
+pub fn part_two(games: &str) -> Option<usize> {
+    sum_powers(&read_file(games).unwrap(), &mut Explain::off())
 }
 
-pub fn part_two(games: &str) -> usize {
-    let games = read_file(games);
+pub fn part_one_buffered(reader: impl BufRead) -> io::Result<usize> {
+    let bag = puzzle_bag();
 
-    find_smallest_possible_guess(&games)
-        .iter()
-        .map(Guess::power)
-        .sum()
-}
+    try_fold_lines(reader, 0, |acc, line| {
+        let game = Game::parse(line)?;
 
-#[derive(Debug, PartialEq)]
-struct Game {
-    id: usize,
-    guesses: Vec<Guess>,
+        Ok(if game.is_feasible(&bag) {
+            acc + game.id
+        } else {
+            acc
+        })
+    })
 }
 
-#[derive(Debug, Default, PartialEq)]
-struct Guess {
-    reds: usize,
-    greens: usize,
-    blues: usize,
+pub fn part_two_buffered(reader: impl BufRead) -> io::Result<Option<usize>> {
+    try_fold_lines(reader, Some(0), |acc, line| {
+        let power = Game::parse(line)?.minimal_bag().power(&PUZZLE_COLOURS);
+
+        Ok(acc
+            .zip(power)
+            .and_then(|(acc, power)| acc.checked_add(power)))
+    })
 }
 
-impl Guess {
-    fn new(reds: usize, greens: usize, blues: usize) -> Self {
-        Self {
-            reds,
-            greens,
-            blues,
-        }
+pub struct Day02;
+
+impl Solution for Day02 {
+    type Parsed<'a> = Vec<Game>;
+
+    fn parse(games: &str) -> ParseResult<Self::Parsed<'_>> {
+        read_file(games)
     }
 
-    fn is_compatible(&self, other: &Self) -> bool {
-        self.reds <= other.reds && self.greens <= other.greens && self.blues <= other.blues
+    fn part_one(games: &Self::Parsed<'_>, explain: &mut Explain) -> String {
+        sum_possible_games(games, explain).to_string()
     }
 
-    fn power(&self) -> usize {
-        self.reds * self.greens * self.blues
+    fn part_two(games: &Self::Parsed<'_>, explain: &mut Explain) -> Option<String> {
+        Some(or_overflow(sum_powers(games, explain)))
+    }
+}
+
+// The colours whose counts the second part multiplies
+pub const PUZZLE_COLOURS: [&str; 3] = ["red", "green", "blue"];
+
+// The bag of the first part
+fn puzzle_bag() -> Cubes {
+    Cubes::new([("red", 12), ("green", 13), ("blue", 14)])
+}
+
+fn sum_possible_games(games: &[Game], explain: &mut Explain) -> usize {
+    let bag = puzzle_bag();
+
+    for game in games {
+        explain.step("game", || {
+            let possible = game.is_feasible(&bag);
+            format!(
+                "{}: {}",
+                game.id,
+                if possible { "possible" } else { "impossible" }
+            )
+        });
     }
+
+    feasible_games(games, &bag).iter().sum()
 }
 
-fn find_possible_games(correct_guess: &Guess, games: &[Game]) -> Vec<usize> {
+fn sum_powers(games: &[Game], explain: &mut Explain) -> Option<usize> {
     games
         .iter()
-        .filter_map(|game| {
-            if game
-                .guesses
-                .iter()
-                .all(|guess| guess.is_compatible(correct_guess))
-            {
-                Some(game.id)
-            } else {
-                None
-            }
+        .zip(minimal_bags(games))
+        .try_fold(0_usize, |sum, (game, bag)| {
+            let power = bag.power(&PUZZLE_COLOURS);
+            explain.step("minimal set", || {
+                format!("{}: {bag}, power {}", game.id, or_overflow(power))
+            });
+
+            sum.checked_add(power?)
         })
-        .collect()
 }
 
-fn find_smallest_possible_guess(games: &[Game]) -> Vec<Guess> {
-    games
-        .iter()
-        .map(|game| {
-            let mut smallest = Guess::default();
+#[derive(Debug, PartialEq)]
+pub struct Game {
+    pub id: usize,
+    // What was shown each time a hand was taken out of the bag
+    pub draws: Vec<Cubes>,
+}
 
-            for guess in &game.guesses {
-                if guess.reds > smallest.reds {
-                    smallest.reds = guess.reds;
-                }
-                if guess.greens > smallest.greens {
-                    smallest.greens = guess.greens;
-                }
-                if guess.blues > smallest.blues {
-                    smallest.blues = guess.blues;
-                }
-            }
+// How many cubes of each colour, the colours not listed have none
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cubes {
+    counts: BTreeMap<String, usize>,
+}
+
+impl Cubes {
+    pub fn new<'a>(counts: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut cubes = Self::default();
 
-            smallest
+        for (colour, count) in counts {
+            cubes.add(colour, count);
+        }
+
+        cubes
+    }
+
+    pub fn add(&mut self, colour: &str, count: usize) {
+        if count > 0 {
+            *self.counts.entry(colour.to_owned()).or_default() += count;
+        }
+    }
+
+    pub fn count(&self, colour: &str) -> usize {
+        self.counts.get(colour).copied().unwrap_or(0)
+    }
+
+    pub fn colours(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.counts
+            .iter()
+            .map(|(colour, &count)| (colour.as_str(), count))
+    }
+
+    // Whether these cubes could all be taken out of the bag at once
+    pub fn fits_in(&self, bag: &Cubes) -> bool {
+        self.colours()
+            .all(|(colour, count)| count <= bag.count(colour))
+    }
+
+    // The fewest cubes holding both these and the other ones
+    pub fn cover(&mut self, other: &Cubes) {
+        for (colour, count) in other.colours() {
+            let covered = self.counts.entry(colour.to_owned()).or_default();
+            *covered = (*covered).max(count);
+        }
+    }
+
+    // The product of the counts of these colours, so one there is none of makes it 0. None when
+    // it doesn't fit
+    pub fn power(&self, colours: &[&str]) -> Option<usize> {
+        colours.iter().try_fold(1_usize, |power, colour| {
+            power.checked_mul(self.count(colour))
         })
-        .collect()
+    }
 }
 
-fn read_file(file: &str) -> Vec<Game> {
-    file.lines()
-        .filter_map(|line| {
-            let line = line.trim();
-            let captures: Vec<&str> = line.split(':').collect();
-            let id = str::parse(&captures[0].replace("Game ", "")).ok()?;
-            let guesses_str = captures[1];
-
-            let guesses: Vec<Guess> = guesses_str
-                .split(';')
-                .map(|guess_str| {
-                    let mut guess = Guess::default();
-
-                    for color in guess_str.split(',') {
-                        let color = color.trim();
-                        let parts: Vec<&str> = color.split(' ').collect();
-                        let number: usize = str::parse(parts[0]).unwrap();
-
-                        match parts[1] {
-                            "red" => guess.reds = number,
-                            "blue" => guess.blues = number,
-                            "green" => guess.greens = number,
-                            _ => panic!("Unexpected color: {}", color),
-                        }
+impl fmt::Display for Cubes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = self
+            .colours()
+            .map(|(colour, count)| format!("{count} {colour}"))
+            .collect::<Vec<_>>();
+
+        f.write_str(&counts.join(", "))
+    }
+}
+
+impl Game {
+    // Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+    fn parse(line: &str) -> ParseResult<Self> {
+        let (game_str, draws_str) = line.split_once(':').required("the ':' after the game")?;
+        // Ids and counts fit in 32 bits, so adding them up can't overflow
+        let id = number::<u32>(game_str.strip_prefix("Game ").required("the game id")?)? as usize;
+
+        let draws = draws_str
+            .split(';')
+            .map(|draw_str| {
+                let mut draw = Cubes::default();
+
+                for cubes in draw_str.split(',') {
+                    let cubes = cubes.trim();
+                    let (count, colour) = cubes.split_once(' ').required("a cube count")?;
+
+                    if colour.is_empty() || colour.contains(char::is_whitespace) {
+                        return Err(ParseError::new(format!("Invalid colour in {cubes:?}")));
                     }
+                    draw.add(colour, number::<u32>(count)? as usize);
+                }
 
-                    guess
-                })
-                .collect();
+                Ok(draw)
+            })
+            .collect::<ParseResult<Vec<_>>>()?;
 
-            Some(Game { id, guesses })
-        })
+        Ok(Game { id, draws })
+    }
+
+    pub fn is_feasible(&self, bag: &Cubes) -> bool {
+        self.draws.iter().all(|draw| draw.fits_in(bag))
+    }
+
+    // The smallest bag the game is feasible with
+    pub fn minimal_bag(&self) -> Cubes {
+        smallest_bag([self])
+    }
+}
+
+// The ids of the games that could have been played with the bag
+pub fn feasible_games(games: &[Game], bag: &Cubes) -> Vec<usize> {
+    games
+        .iter()
+        .filter(|game| game.is_feasible(bag))
+        .map(|game| game.id)
         .collect()
 }
 
+pub fn minimal_bags(games: &[Game]) -> Vec<Cubes> {
+    games.iter().map(Game::minimal_bag).collect()
+}
+
+// The smallest bag all the games are feasible with
+pub fn smallest_bag<'a>(games: impl IntoIterator<Item = &'a Game>) -> Cubes {
+    let mut bag = Cubes::default();
+
+    for draw in games.into_iter().flat_map(|game| &game.draws) {
+        bag.cover(draw);
+    }
+
+    bag
+}
+
+pub fn read_file(file: &str) -> ParseResult<Vec<Game>> {
+    input::lines(file).map(Game::parse).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn rgb(reds: usize, greens: usize, blues: usize) -> Cubes {
+        Cubes::new([("red", reds), ("green", greens), ("blue", blues)])
+    }
+
     #[test]
     fn example_one() {
         let games = "\
@@ -133,81 +259,59 @@ mod tests {
         Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
         Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
-        let games = read_file(games);
+        let games = read_file(games).unwrap();
         let mut iter = games.iter();
 
         assert_eq!(
             &Game {
                 id: 1,
-                guesses: vec![
-                    Guess::new(4, 0, 3),
-                    Guess::new(1, 2, 6),
-                    Guess::new(0, 2, 0)
-                ]
+                draws: vec![rgb(4, 0, 3), rgb(1, 2, 6), rgb(0, 2, 0)]
             },
             iter.next().unwrap()
         );
         assert_eq!(
             &Game {
                 id: 2,
-                guesses: vec![
-                    Guess::new(0, 2, 1),
-                    Guess::new(1, 3, 4),
-                    Guess::new(0, 1, 1)
-                ]
+                draws: vec![rgb(0, 2, 1), rgb(1, 3, 4), rgb(0, 1, 1)]
             },
             iter.next().unwrap()
         );
         assert_eq!(
             &Game {
                 id: 3,
-                guesses: vec![
-                    Guess::new(20, 8, 6),
-                    Guess::new(4, 13, 5),
-                    Guess::new(1, 5, 0)
-                ]
+                draws: vec![rgb(20, 8, 6), rgb(4, 13, 5), rgb(1, 5, 0)]
             },
             iter.next().unwrap()
         );
         assert_eq!(
             &Game {
                 id: 4,
-                guesses: vec![
-                    Guess::new(3, 1, 6),
-                    Guess::new(6, 3, 0),
-                    Guess::new(14, 3, 15)
-                ]
+                draws: vec![rgb(3, 1, 6), rgb(6, 3, 0), rgb(14, 3, 15)]
             },
             iter.next().unwrap()
         );
         assert_eq!(
             &Game {
                 id: 5,
-                guesses: vec![Guess::new(6, 3, 1), Guess::new(1, 2, 2)]
+                draws: vec![rgb(6, 3, 1), rgb(1, 2, 2)]
             },
             iter.next().unwrap()
         );
         assert_eq!(None, iter.next());
 
-        let correct_guess = Guess::new(12, 13, 14);
-
-        assert!([1, 2, 5]
-            .iter()
-            .eq(find_possible_games(&correct_guess, &games).iter()));
+        assert_eq!(vec![1, 2, 5], feasible_games(&games, &puzzle_bag()));
     }
 
     #[test]
     fn full_one() {
         let games = include_str!("../res/day_02.txt");
 
-        let games = read_file(games);
-
-        let correct_guess = Guess::new(12, 13, 14);
+        let games = read_file(games).unwrap();
 
         assert_eq!(100, games.len());
         assert_eq!(
-            2685 as usize,
-            find_possible_games(&correct_guess, &games).iter().sum()
+            2685_usize,
+            feasible_games(&games, &puzzle_bag()).iter().sum()
         );
     }
 
@@ -220,23 +324,99 @@ mod tests {
         Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
         Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
-        let games = read_file(games);
-        let powers: Vec<usize> = find_smallest_possible_guess(&games)
+        let games = read_file(games).unwrap();
+        let powers: Vec<usize> = minimal_bags(&games)
             .iter()
-            .map(Guess::power)
+            .filter_map(|bag| bag.power(&PUZZLE_COLOURS))
             .collect();
 
         assert!([48, 12, 1560, 630, 36].iter().eq(powers.iter()));
+
+        let mut explain = Explain::on();
+        Day02::part_two(&games, &mut explain);
+        assert_eq!(
+            "minimal set: 1: 6 blue, 2 green, 4 red, power 48",
+            explain.steps()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn extra_colours() {
+        let games = "\
+        Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue, 2 yellow
+        Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red
+        Game 7: 2 yellow, 1 yellow; 3 purple";
+
+        let games = read_file(games).unwrap();
+        assert_eq!(
+            Cubes::new([("yellow", 3), ("purple", 3)]),
+            games[2].minimal_bag()
+        );
+        assert_eq!(vec![2], feasible_games(&games, &puzzle_bag()));
+
+        let bag = smallest_bag([&games[0], &games[2]]);
+        assert_eq!(
+            "6 blue, 2 green, 3 purple, 4 red, 3 yellow",
+            bag.to_string()
+        );
+        assert_eq!(vec![1, 7], feasible_games(&games, &bag));
+
+        let mut covered = bag.clone();
+        covered.cover(&Cubes::new([("yellow", 1)]));
+        assert_eq!(bag, covered);
+        assert!(!rgb(4, 2, 6).fits_in(&rgb(4, 2, 5)));
+        assert!(read_file("Game 1: 3 light blue").is_err());
+        assert!(read_file("Game 1: 3").is_err());
+    }
+
+    #[test]
+    fn missing_colour() {
+        let games = "\
+        Game 1: 3 blue, 4 red; 1 red, 6 blue
+        Game 2: 2 green";
+
+        assert_eq!(Some(0), part_two(games));
+        assert_eq!(Some(0), part_two_buffered(games.as_bytes()).unwrap());
+
+        let bag = read_file(games).unwrap()[0].minimal_bag();
+        assert_eq!(Some(0), bag.power(&PUZZLE_COLOURS));
+        assert_eq!(Some(24), bag.power(&["red", "blue"]));
+        assert_eq!(Some(0), Cubes::new([]).power(&PUZZLE_COLOURS));
+    }
+
+    #[test]
+    fn overflow() {
+        let games = "\
+        Game 1: 4294967295 red, 4294967295 green, 4294967295 blue
+        Game 2: 1 red, 1 green, 1 blue";
+
+        assert_eq!(None, part_two(games));
+        assert_eq!(None, part_two_buffered(games.as_bytes()).unwrap());
+        assert_eq!(
+            "overflow",
+            Day02::part_two(&read_file(games).unwrap(), &mut Explain::off()).unwrap()
+        );
+        assert_eq!(Some(1), part_two("Game 1: 1 red, 1 green, 1 blue"));
+        assert!(read_file("Game 4294967296: 1 red").is_err());
+        assert!(read_file("Game 1: 4294967296 red").is_err());
+    }
+
+    #[test]
+    fn buffered() {
+        let games = include_str!("../res/day_02.txt");
+
+        assert_eq!(2685, part_one_buffered(games.as_bytes()).unwrap());
+        assert_eq!(Some(83707), part_two_buffered(games.as_bytes()).unwrap());
     }
 
     #[test]
     fn full_two() {
         let games = include_str!("../res/day_02.txt");
 
-        let games = read_file(games);
-        let powers: usize = find_smallest_possible_guess(&games)
+        let games = read_file(games).unwrap();
+        let powers: usize = minimal_bags(&games)
             .iter()
-            .map(Guess::power)
+            .filter_map(|bag| bag.power(&PUZZLE_COLOURS))
             .sum();
 
         assert_eq!(83707, powers);
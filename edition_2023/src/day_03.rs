@@ -1,49 +1,156 @@
-use std::ops::Range;
+use std::{collections::HashMap, ops::Range};
+
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseResult, Required},
+    solution::Solution,
+};
 
 pub fn part_one(engine_schema_str: &str) -> usize {
-    let schema = parse_engine_schema(engine_schema_str);
+    let schema = parse_engine_schema(engine_schema_str).unwrap();
     schema.sum_parts()
 }
 
 pub fn part_two(engine_schema_str: &str) -> usize {
-    let schema = parse_engine_schema(engine_schema_str);
+    let schema = parse_engine_schema(engine_schema_str).unwrap();
     schema.gear_power()
 }
 
-#[derive(Debug, PartialEq)]
-struct PartNumber {
-    number: usize,
-    line: usize,
-    columns: Range<usize>,
-    is_part_number: bool,
-}
+pub struct Day03;
+
+impl Solution for Day03 {
+    type Parsed<'a> = EngineSchema;
 
-impl PartNumber {
-    fn is_touching(&self, symbol: &Symbol) -> bool {
-        symbol.line.abs_diff(self.line) < 2 && self.range_of_contact().contains(&symbol.column)
+    fn parse(engine_schema_str: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse_engine_schema(engine_schema_str)
     }
 
-    fn range_of_contact(&self) -> Range<usize> {
-        self.columns.start.saturating_sub(1)..(self.columns.end + 1)
+    fn part_one(schema: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        schema.sum_parts().to_string()
+    }
+
+    fn part_two(schema: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(schema.gear_power().to_string())
     }
 }
 
-#[derive(Debug)]
-struct Symbol {
-    char: char,
-    line: usize,
-    column: usize,
-    gear_power: Option<usize>,
+#[derive(Debug, PartialEq)]
+pub struct PartNumber {
+    pub number: usize,
+    pub line: usize,
+    pub columns: Range<usize>,
+    // Whether a symbol is next to it
+    pub is_part_number: bool,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Symbol {
+    pub char: char,
+    pub line: usize,
+    pub column: usize,
+}
+
+// Parts and symbols are referred to by their index, both in order of encounter. The cells they
+// cover are indexed, so their neighbours are found without going through the whole schema
 #[derive(Debug)]
-// both vecs are on order of encounter
-struct EngineSchema {
+pub struct EngineSchema {
     parts: Vec<PartNumber>,
     symbols: Vec<Symbol>,
+    part_cells: HashMap<(usize, usize), usize>,
+    symbol_cells: HashMap<(usize, usize), usize>,
+}
+
+// The cells of the line and columns and the ones around them. Nothing is left of column 0 or
+// above line 0, and a number ending a line isn't next to the start of the following one
+fn around(line: usize, columns: Range<usize>) -> impl Iterator<Item = (usize, usize)> {
+    (line.saturating_sub(1)..=line + 1).flat_map(move |line| {
+        (columns.start.saturating_sub(1)..=columns.end).map(move |column| (line, column))
+    })
+}
+
+// The gears of the puzzle
+fn is_star_pair(char: char, neighbours: usize) -> bool {
+    char == '*' && neighbours == 2
 }
 
 impl EngineSchema {
+    fn new(parts: Vec<PartNumber>, symbols: Vec<Symbol>) -> Self {
+        let part_cells = parts
+            .iter()
+            .enumerate()
+            .flat_map(|(id, part)| {
+                part.columns
+                    .clone()
+                    .map(move |column| ((part.line, column), id))
+            })
+            .collect();
+        let symbol_cells = symbols
+            .iter()
+            .enumerate()
+            .map(|(id, symbol)| ((symbol.line, symbol.column), id))
+            .collect();
+
+        let mut schema = Self {
+            parts,
+            symbols,
+            part_cells,
+            symbol_cells,
+        };
+        for id in 0..schema.parts.len() {
+            schema.parts[id].is_part_number = !schema.symbols_next_to(id).is_empty();
+        }
+
+        schema
+    }
+
+    pub fn parts(&self) -> &[PartNumber] {
+        &self.parts
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    // Ids of the parts touching the symbol, even by a corner
+    pub fn parts_next_to(&self, symbol: usize) -> Vec<usize> {
+        let Symbol { line, column, .. } = self.symbols[symbol];
+        let mut parts = around(line, column..column + 1)
+            .filter_map(|cell| self.part_cells.get(&cell).copied())
+            .collect::<Vec<_>>();
+        parts.sort_unstable();
+        parts.dedup();
+
+        parts
+    }
+
+    // Ids of the symbols touching the part, even by a corner
+    pub fn symbols_next_to(&self, part: usize) -> Vec<usize> {
+        let PartNumber { line, columns, .. } = &self.parts[part];
+        let mut symbols = around(*line, columns.clone())
+            .filter_map(|cell| self.symbol_cells.get(&cell).copied())
+            .collect::<Vec<_>>();
+        symbols.sort_unstable();
+
+        symbols
+    }
+
+    // Ids of the symbols that are gears, told by their char and how many parts they touch
+    pub fn gears(&self, is_gear: impl Fn(char, usize) -> bool) -> Vec<usize> {
+        (0..self.symbols.len())
+            .filter(|&id| is_gear(self.symbols[id].char, self.parts_next_to(id).len()))
+            .collect()
+    }
+
+    // The product of the parts touching the symbol, None when it doesn't fit
+    pub fn ratio(&self, symbol: usize) -> Option<usize> {
+        self.parts_next_to(symbol)
+            .iter()
+            .try_fold(1_usize, |ratio, &part| {
+                ratio.checked_mul(self.parts[part].number)
+            })
+    }
+
     fn sum_parts(&self) -> usize {
         self.parts
             .iter()
@@ -52,34 +159,20 @@ impl EngineSchema {
             .sum()
     }
 
+    // The ratios and their sum were checked when parsing
     fn gear_power(&self) -> usize {
-        self.symbols.iter().filter_map(|s| s.gear_power).sum()
-    }
-
-    fn filter_active_parts(&mut self) {
-        for symbol in self.symbols.iter_mut() {
-            let part_neighbors = self
-                .parts
-                .iter_mut()
-                .take_while(|p| p.line < symbol.line + 2)
-                .filter(|p| p.is_touching(symbol))
-                .collect::<Vec<_>>();
-
-            if symbol.char == '*' && part_neighbors.len() == 2 {
-                symbol.gear_power = Some(part_neighbors[0].number * part_neighbors[1].number);
-            }
-            for part in part_neighbors {
-                part.is_part_number = true;
-            }
-        }
+        self.gears(is_star_pair)
+            .into_iter()
+            .filter_map(|gear| self.ratio(gear))
+            .sum()
     }
 }
 
-fn parse_engine_schema(schema_str: &str) -> EngineSchema {
+fn parse_engine_schema(schema_str: &str) -> ParseResult<EngineSchema> {
     let mut parts = Vec::<PartNumber>::new();
     let mut symbols = Vec::<Symbol>::new();
 
-    for (line_number, line) in schema_str.lines().enumerate() {
+    for (line_number, line) in input::lines(schema_str).enumerate() {
         let mut column_iter = line.chars().enumerate().peekable();
 
         while let Some((column_number, char)) = column_iter.next() {
@@ -95,8 +188,9 @@ fn parse_engine_schema(schema_str: &str) -> EngineSchema {
                         column_iter.next();
                     }
 
+                    // Part numbers fit in 32 bits, so their sum can't overflow
                     parts.push(PartNumber {
-                        number: number_str.parse().unwrap(),
+                        number: number::<u32>(&number_str)? as usize,
                         line: line_number,
                         columns: (column_number..(column_number + number_str.len())),
                         is_part_number: false,
@@ -108,16 +202,20 @@ fn parse_engine_schema(schema_str: &str) -> EngineSchema {
                         char,
                         line: line_number,
                         column: column_number,
-                        gear_power: None,
                     });
                 }
             }
         }
     }
 
-    let mut schema = EngineSchema { parts, symbols };
-    schema.filter_active_parts();
+    let schema = EngineSchema::new(parts, symbols);
     schema
+        .gears(is_star_pair)
+        .into_iter()
+        .try_fold(0_usize, |sum, gear| sum.checked_add(schema.ratio(gear)?))
+        .required("gear ratios that add up without overflowing")?;
+
+    Ok(schema)
 }
 
 #[cfg(test)]
@@ -138,11 +236,60 @@ mod tests {
 ...$.*....
 .664.598..";
 
-        let schema = parse_engine_schema(input);
+        let schema = parse_engine_schema(input).unwrap();
 
         assert_eq!(6, schema.symbols.len());
         assert_eq!(4361, schema.sum_parts());
         assert_eq!(467835, schema.gear_power());
+
+        assert_eq!(vec![0, 2], schema.parts_next_to(0));
+        assert_eq!(vec![1], schema.symbols_next_to(3));
+        assert_eq!(vec![0, 5], schema.gears(is_star_pair));
+        assert_eq!(
+            vec![2],
+            schema.gears(|char, parts| char == '*' && parts == 1)
+        );
+        assert_eq!(Some(617), schema.ratio(2));
+    }
+
+    #[test]
+    fn grid_edges() {
+        let input = "\
+..12
+#..#
+45..";
+
+        let schema = parse_engine_schema(input).unwrap();
+
+        // A number ending a line doesn't touch the symbol starting the next one, nor a symbol
+        // ending a line the number starting the next one. Each only touches the one above or
+        // below it
+        assert_eq!(vec![1], schema.parts_next_to(0));
+        assert_eq!(vec![0], schema.parts_next_to(1));
+        assert_eq!(vec![1], schema.symbols_next_to(0));
+        assert_eq!(vec![0], schema.symbols_next_to(1));
+        assert_eq!(
+            vec![true, true],
+            schema
+                .parts()
+                .iter()
+                .map(|p| p.is_part_number)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![0, 1],
+            schema.gears(|char, parts| char == '#' && parts == 1)
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let gear = "4294967295*4294967295";
+        assert_eq!(8589934590, part_one(gear));
+        assert_eq!(18446744065119617025, part_two(gear));
+
+        assert!(parse_engine_schema(&format!("{gear}.{gear}")).is_err());
+        assert!(parse_engine_schema("4294967296*1").is_err());
     }
 
     #[test]
@@ -161,7 +308,7 @@ mod tests {
 .*.........*
 1.1..503+.56";
 
-        let schema = parse_engine_schema(input);
+        let schema = parse_engine_schema(input).unwrap();
 
         assert_eq!(925, schema.sum_parts());
         assert_eq!(6756, schema.gear_power());
@@ -174,7 +321,7 @@ mod tests {
 ....691........341.262..36.549...........386........437.............................662...........848............#......*...................
 .......................*..........936...*...............................-...........*......516....%......358....707..535...........841......";
 
-        let schema = parse_engine_schema(input);
+        let schema = parse_engine_schema(input).unwrap();
 
         assert_eq!(
             Some(&PartNumber {
@@ -209,7 +356,7 @@ mod tests {
     fn full_schema() {
         let input = include_str!("../res/day_03.txt");
 
-        let schema = parse_engine_schema(input);
+        let schema = parse_engine_schema(input).unwrap();
 
         assert_eq!(533775, schema.sum_parts());
         assert_eq!(78236071, schema.gear_power());
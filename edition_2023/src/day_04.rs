@@ -1,79 +1,217 @@
-use std::{cell::RefCell, collections::HashSet};
+use std::{
+    collections::{HashSet, VecDeque},
+    io::{self, BufRead},
+};
 
-pub fn part_one(cards_str: &str) -> usize {
-    parse_cards(cards_str).iter().map(calculate_points).sum()
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{numbers, ParseResult, Required},
+    solution::{or_overflow, Solution},
+    stream::try_fold_lines,
+};
+
+pub fn part_one(cards_str: &str) -> Option<usize> {
+    total(parse_cards(cards_str).unwrap().points())
 }
 
-pub fn part_two(cards_str: &str) -> usize {
-    let cards_references: Vec<RefCell<Card>> = parse_cards(cards_str)
-        .into_iter()
-        .map(RefCell::new)
-        .collect();
+pub fn part_two(cards_str: &str) -> Option<usize> {
+    let copies = parse_cards(cards_str).unwrap().copies(Rules::default());
 
-    let mut total = cards_references.len();
+    total(copies.into_iter().map(Some))
+}
 
-    for card_rc in &cards_references {
-        let card = card_rc.borrow();
+pub struct Day04;
 
-        if card.matches == 0 {
-            continue;
+impl Solution for Day04 {
+    type Parsed<'a> = Scratchcards;
+
+    fn parse(cards_str: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse_cards(cards_str)
+    }
+
+    fn part_one(scratchcards: &Self::Parsed<'_>, explain: &mut Explain) -> String {
+        let points = scratchcards.points();
+
+        for (card, points) in scratchcards.cards.iter().zip(&points) {
+            explain.step("points", || {
+                format!(
+                    "card {}: {} matches, {} points",
+                    card.number,
+                    card.matches,
+                    or_overflow(*points)
+                )
+            });
         }
 
-        for i in card.number..(card.number + card.matches) {
-            if let Some(copy) = cards_references.get(i) {
-                copy.borrow_mut().copies += card.copies;
-                total += card.copies;
-            } else {
-                break;
-            }
+        or_overflow(total(points))
+    }
+
+    fn part_two(scratchcards: &Self::Parsed<'_>, explain: &mut Explain) -> Option<String> {
+        let copies = scratchcards.copies(Rules::default());
+
+        for (card, copies) in scratchcards.cards.iter().zip(&copies) {
+            explain.step("copies", || {
+                format!(
+                    "card {}: {copies} copies, {} matches",
+                    card.number, card.matches
+                )
+            });
         }
+
+        Some(or_overflow(total(copies.into_iter().map(Some))))
     }
+}
 
-    total
+pub fn part_one_buffered(reader: impl BufRead) -> io::Result<Option<usize>> {
+    let mut number = 0;
+
+    try_fold_lines(reader, Some(0), |acc, line| {
+        number += 1;
+        let points = calculate_points(&Card::parse(line, number)?);
+
+        Ok(total([acc, points]))
+    })
 }
 
-#[derive(Debug)]
-struct Card {
-    number: usize,
-    copies: usize,
-    matches: usize,
+pub fn part_two_buffered(reader: impl BufRead) -> io::Result<Option<usize>> {
+    // Copies won by cards that were not read yet, starting at the next one
+    let mut pending = VecDeque::new();
+    let mut number = 0;
+
+    try_fold_lines(reader, Some(0), |sum, line| {
+        number += 1;
+        let card = Card::parse(line, number)?;
+        let copies = pending.pop_front().unwrap_or(0_usize).saturating_add(1);
+
+        if pending.len() < card.matches {
+            pending.resize(card.matches, 0);
+        }
+        for won in pending.iter_mut().take(card.matches) {
+            *won = won.saturating_add(copies);
+        }
+
+        Ok(total([sum, Some(copies)]))
+    })
 }
 
-fn parse_cards(file: &str) -> Vec<Card> {
-    file.lines()
-        .enumerate()
-        .map(|(i, line)| {
-            let line = line.trim();
-            let card_and_numbers = line.split(':').collect::<Vec<_>>();
-            let numbers = card_and_numbers[1].trim();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub number: usize,
+    pub matches: usize,
+}
+
+impl Card {
+    fn parse(line: &str, number: usize) -> ParseResult<Self> {
+        let (_, card_numbers) = line.split_once(':').required("the ':' after the card")?;
+        let (winning, mine) = card_numbers
+            .split_once('|')
+            .required("the '|' between numbers")?;
+
+        let winning = numbers::<usize>(winning)?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let mine = numbers::<usize>(mine)?.into_iter().collect::<HashSet<_>>();
+        let matches = mine.intersection(&winning).count();
+
+        Ok(Card { number, matches })
+    }
+}
+
+// Which cards the copies won by a card go to, as many cards as it has matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Cascade {
+    // The following cards, none past the last one
+    #[default]
+    Next,
+    // The cards before it, none before the first one
+    Previous,
+    // The following cards, going on from the first one after the last
+    Wrap,
+}
+
+// The puzzle's rules are the default ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub cascade: Cascade,
+    // The most copies of a card there can be, the original included
+    pub max_copies: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scratchcards {
+    cards: Vec<Card>,
+}
 
-            let winning_and_mine = numbers.split('|').collect::<Vec<_>>();
+impl Scratchcards {
+    pub fn new(cards: Vec<Card>) -> Self {
+        Self { cards }
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    // None for the points that don't fit
+    pub fn points(&self) -> Vec<Option<usize>> {
+        self.cards.iter().map(calculate_points).collect()
+    }
 
-            let winning = parse_numbers(winning_and_mine[0]);
-            let mine = parse_numbers(winning_and_mine[1]);
-            let matches = mine.intersection(&winning).count();
+    // How many copies of each card end up scratched, the original included. Each card is
+    // scratched once in the order the copies flow, so with `Next` and `Previous` a card has all
+    // its copies by its turn. With `Wrap`, the copies a card wins after its turn don't win more
+    pub fn copies(&self, rules: Rules) -> Vec<usize> {
+        let n = self.cards.len();
+        let mut copies = vec![1_usize; n];
+        let backwards = rules.cascade == Cascade::Previous;
 
-            Card {
-                number: i + 1,
-                copies: 1,
-                matches,
+        for i in (0..n).map(|i| if backwards { n - 1 - i } else { i }) {
+            let matches = self.cards[i].matches;
+            let won = match rules.cascade {
+                Cascade::Next => (i + 1..n.min(i + 1 + matches)).collect::<Vec<_>>(),
+                Cascade::Previous => (i.saturating_sub(matches)..i).collect(),
+                // Never back to the card itself
+                Cascade::Wrap => (1..=matches.min(n - 1))
+                    .map(|offset| (i + offset) % n)
+                    .collect(),
+            };
+
+            for j in won {
+                copies[j] = copies[j].saturating_add(copies[i]);
+                if let Some(max) = rules.max_copies {
+                    copies[j] = copies[j].min(max);
+                }
             }
-        })
-        .collect()
+        }
+
+        copies
+    }
 }
 
-fn calculate_points(card: &Card) -> usize {
+fn parse_cards(file: &str) -> ParseResult<Scratchcards> {
+    let cards = input::lines(file)
+        .enumerate()
+        .map(|(i, line)| Card::parse(line, i + 1))
+        .collect::<ParseResult<_>>()?;
+
+    Ok(Scratchcards::new(cards))
+}
+
+fn calculate_points(card: &Card) -> Option<usize> {
     match card.matches {
-        0 => 0,
-        number => 2_usize.pow((number - 1) as u32),
+        0 => Some(0),
+        number => u32::try_from(number - 1)
+            .ok()
+            .and_then(|shift| 1_usize.checked_shl(shift)),
     }
 }
 
-fn parse_numbers(numbers: &str) -> HashSet<usize> {
-    numbers
-        .split_whitespace()
-        .map(|number| number.parse::<usize>().unwrap())
-        .collect()
+// Copies saturate, and as every card has at least one a saturated count makes the total overflow
+// too, so it is reported like any other sum that doesn't fit
+fn total(counts: impl IntoIterator<Item = Option<usize>>) -> Option<usize> {
+    counts
+        .into_iter()
+        .try_fold(0_usize, |sum, count| sum.checked_add(count?))
 }
 
 #[cfg(test)]
@@ -90,15 +228,86 @@ mod tests {
         Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
         Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-        assert_eq!(13, part_one(input));
-        assert_eq!(30, part_two(input));
+        assert_eq!(Some(13), part_one(input));
+        assert_eq!(Some(30), part_two(input));
+        assert_eq!(Some(13), part_one_buffered(input.as_bytes()).unwrap());
+        assert_eq!(Some(30), part_two_buffered(input.as_bytes()).unwrap());
+
+        let mut explain = Explain::on();
+        Day04::part_two(&Day04::parse(input).unwrap(), &mut explain);
+        assert_eq!(
+            "copies: card 5: 14 copies, 0 matches",
+            explain.steps()[4].to_string()
+        );
+    }
+
+    #[test]
+    fn cascade_rules() {
+        let input = "\
+        Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+        Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+        Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+        Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+        Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+        Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+        let scratchcards = parse_cards(input).unwrap();
+        let copies = |cascade, max_copies| {
+            scratchcards.copies(Rules {
+                cascade,
+                max_copies,
+            })
+        };
+
+        assert_eq!(
+            vec![Some(8), Some(2), Some(2), Some(1), Some(0), Some(0)],
+            scratchcards.points()
+        );
+        assert_eq!(vec![1, 2, 4, 8, 14, 1], copies(Cascade::Next, None));
+        assert_eq!(vec![1, 2, 4, 5, 5, 1], copies(Cascade::Next, Some(5)));
+        assert_eq!(vec![6, 3, 2, 1, 1, 1], copies(Cascade::Previous, None));
+        assert_eq!(copies(Cascade::Next, None), copies(Cascade::Wrap, None));
+
+        let card = |number, matches| Card { number, matches };
+        let wrapping = Scratchcards::new(vec![card(1, 0), card(2, 0), card(3, 5)]);
+        assert_eq!(
+            vec![2, 2, 1],
+            wrapping.copies(Rules {
+                cascade: Cascade::Wrap,
+                max_copies: None
+            })
+        );
+        assert_eq!(vec![1, 1, 1], wrapping.copies(Rules::default()));
+    }
+
+    #[test]
+    fn overflow() {
+        let card = |matches: usize| {
+            let numbers = (1..=matches).map(|n| n.to_string()).collect::<Vec<_>>();
+            format!("Card 1: {0} | {0}\n", numbers.join(" "))
+        };
+
+        assert_eq!(Some(1 << 63), part_one(&card(64)));
+        assert_eq!(None, part_one(&card(65)));
+        assert_eq!(None, part_one_buffered(card(65).as_bytes()).unwrap());
+
+        // Each card wins a copy of every later one, so the last has 2^69 copies
+        let cards = (0..70).rev().map(card).collect::<String>();
+        assert_eq!(None, part_two(&cards));
+        assert_eq!(None, part_two_buffered(cards.as_bytes()).unwrap());
+        assert_eq!(
+            "overflow",
+            Day04::part_two(&Day04::parse(&cards).unwrap(), &mut Explain::off()).unwrap()
+        );
     }
 
     #[test]
     fn real() {
         let input = include_str!("../res/day_04.txt");
 
-        assert_eq!(23847, part_one(input));
-        assert_eq!(8570000, part_two(input));
+        assert_eq!(Some(23847), part_one(input));
+        assert_eq!(Some(8570000), part_two(input));
+        assert_eq!(Some(23847), part_one_buffered(input.as_bytes()).unwrap());
+        assert_eq!(Some(8570000), part_two_buffered(input.as_bytes()).unwrap());
     }
 }
@@ -1,13 +1,44 @@
 use std::ops::Range;
 
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{numbers, ParseError, ParseResult, Required},
+    solution::Solution,
+};
+
 pub fn part_one(almanac_str: &str) -> usize {
-    let almanac = Almanac::parse(almanac_str);
+    let almanac = Almanac::parse(almanac_str).unwrap();
     almanac.find_min_location(almanac.seeds.clone().into_iter())
 }
 
 pub fn part_two(almanac_str: &str) -> usize {
-    let almanac = Almanac::parse(almanac_str);
-    almanac.find_min_location_back(almanac.seeds_as_ranges())
+    let almanac = Almanac::parse(almanac_str).unwrap();
+    almanac.find_min_location_of_ranges(almanac.seeds_as_ranges())
+}
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    type Parsed<'a> = Almanac;
+
+    fn parse(almanac_str: &str) -> ParseResult<Self::Parsed<'_>> {
+        Almanac::parse(almanac_str)
+    }
+
+    fn part_one(almanac: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        almanac
+            .find_min_location(almanac.seeds.iter().copied())
+            .to_string()
+    }
+
+    fn part_two(almanac: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(
+            almanac
+                .find_min_location_of_ranges(almanac.seeds_as_ranges())
+                .to_string(),
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -25,16 +56,6 @@ impl RangeMap {
         }
     }
 
-    fn try_reverse_map(&self, n: usize) -> Option<usize> {
-        if n >= self.add_deviation(self.input_range.start)
-            && n < self.add_deviation(self.input_range.end)
-        {
-            Some((n as isize - self.deviation) as usize)
-        } else {
-            None
-        }
-    }
-
     fn add_deviation(&self, n: usize) -> usize {
         (n as isize + self.deviation) as usize
     }
@@ -46,25 +67,24 @@ struct Mapping {
 }
 
 impl Mapping {
-    fn parse(ranges_str: Vec<&str>) -> Self {
+    fn parse(ranges_str: &[&str]) -> ParseResult<Self> {
         let mut ranges: Vec<RangeMap> = ranges_str
             .iter()
             .map(|line| {
-                let parts = line
-                    .split_whitespace()
-                    .map(|num| num.parse::<usize>().unwrap())
-                    .collect::<Vec<_>>();
-
-                RangeMap {
-                    input_range: (parts[1]..(parts[1] + parts[2])),
-                    deviation: parts[0] as isize - parts[1] as isize,
-                }
+                let [destination, source, length] = almanac_numbers(line)?[..] else {
+                    return Err(ParseError::new(format!("Expected 3 numbers in {line:?}")));
+                };
+
+                Ok(RangeMap {
+                    input_range: (source..(source + length)),
+                    deviation: destination as isize - source as isize,
+                })
             })
-            .collect();
+            .collect::<ParseResult<_>>()?;
 
-        ranges.sort_by(|a, b| a.input_range.start.cmp(&b.input_range.start));
+        ranges.sort_by_key(|range| range.input_range.start);
 
-        Self { ranges }
+        Ok(Self { ranges })
     }
 
     fn get(&self, input: usize) -> usize {
@@ -74,38 +94,64 @@ impl Mapping {
             .unwrap_or(input)
     }
 
-    fn reverse_get(&self, input: usize) -> usize {
-        self.ranges
-            .iter()
-            .find_map(|range| range.try_reverse_map(input))
-            .unwrap_or(input)
+    // Where a whole range of numbers goes, cut where the map's ranges start and end. Like `get`,
+    // the range starting first wins where they overlap
+    fn get_ranges(&self, input: Range<usize>) -> Vec<Range<usize>> {
+        let mut mapped = Vec::new();
+        let mut start = input.start;
+
+        for range in &self.ranges {
+            let from = range.input_range.start.max(start);
+            let to = range.input_range.end.min(input.end);
+
+            if from >= to {
+                continue;
+            }
+            if start < from {
+                mapped.push(start..from);
+            }
+
+            mapped.push(range.add_deviation(from)..range.add_deviation(to));
+            start = to;
+        }
+
+        if start < input.end {
+            mapped.push(start..input.end);
+        }
+
+        mapped
     }
 }
 
 #[derive(Debug)]
-struct Almanac {
+pub struct Almanac {
     seeds: Vec<usize>,
     maps: Vec<Mapping>,
 }
 
 impl Almanac {
-    fn parse(almanac_str: &str) -> Self {
-        let almanac_str = almanac_str.replace('\r', "");
-        let mut sections = almanac_str.split("\n\n");
+    fn parse(almanac_str: &str) -> ParseResult<Self> {
+        let sections = input::sections(almanac_str);
 
-        let seeds: Vec<usize> = sections
-            .next()
-            .unwrap()
-            .replace("seeds: ", "")
-            .split_whitespace()
-            .map(|num| num.parse().unwrap())
-            .collect();
+        let seeds = sections.first().required("the seeds")?[0];
+        let seeds = almanac_numbers(seeds.trim_start_matches("seeds:"))?;
 
-        let maps: Vec<Mapping> = sections
-            .map(|text| Mapping::parse(text.lines().skip(1).collect()))
-            .collect();
+        // The second part reads them as ranges, and searches until it finds one of them
+        if seeds.is_empty() || seeds.len() % 2 != 0 {
+            return Err(ParseError::new(
+                "Expected the seeds as pairs of a start and a length",
+            ));
+        }
+        if seeds.chunks_exact(2).any(|start_size| start_size[1] == 0) {
+            return Err(ParseError::new("Expected seed ranges that aren't empty"));
+        }
 
-        Self { seeds, maps }
+        let maps = sections[1..]
+            .iter()
+            .map(|section| Mapping::parse(&section[1..]))
+            .collect::<ParseResult<_>>()?;
+
+        Ok(Self { seeds, maps })
     }
 
     fn find_min_location(&self, seeds: impl Iterator<Item = usize>) -> usize {
@@ -126,20 +172,19 @@ impl Almanac {
         min
     }
 
-    fn find_min_location_back(&self, seeds: Vec<Range<usize>>) -> usize {
-        let mut n = 0;
-        loop {
-            let mut key = n;
-
-            for map in self.maps.iter().rev() {
-                key = map.reverse_get(key)
-            }
-
-            if seeds.iter().any(|r| r.contains(&key)) {
-                return n;
-            }
-            n += 1;
-        }
+    fn find_min_location_of_ranges(&self, seeds: Vec<Range<usize>>) -> usize {
+        self.maps
+            .iter()
+            .fold(seeds, |ranges, map| {
+                ranges
+                    .into_iter()
+                    .flat_map(|range| map.get_ranges(range))
+                    .collect()
+            })
+            .iter()
+            .map(|range| range.start)
+            .min()
+            .expect("Parsing made sure there are seeds")
     }
 
     fn seeds_as_ranges(&self) -> Vec<Range<usize>> {
@@ -154,16 +199,128 @@ impl Almanac {
             })
             .collect::<Vec<_>>();
 
-        ranges.sort_by(|a, b| a.start.cmp(&b.start));
+        ranges.sort_by_key(|range| range.start);
 
         ranges
     }
 }
 
+// Numbers in the almanac fit in 32 bits, so ends and deviations can't overflow
+fn almanac_numbers(line: &str) -> ParseResult<Vec<usize>> {
+    Ok(numbers::<u32>(line)?
+        .into_iter()
+        .map(|n| n as usize)
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
+    // (destination, source, length) lines
+    type Map = Vec<(usize, usize, usize)>;
+
+    // Each map shuffles consecutive segments of a window around, like the real ones
+    fn map() -> impl Strategy<Value = Map> {
+        prop::collection::vec(1..8_usize, 1..5).prop_flat_map(|lengths| {
+            let order = Just((0..lengths.len()).collect::<Vec<_>>()).prop_shuffle();
+
+            (Just(lengths), order, 0..20_usize).prop_map(|(lengths, order, base)| {
+                let source_starts = lengths
+                    .iter()
+                    .scan(base, |start, length| {
+                        *start += length;
+                        Some(*start - length)
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut destination = base;
+                let mut lines = Vec::new();
+                for segment in order {
+                    lines.push((destination, source_starts[segment], lengths[segment]));
+                    destination += lengths[segment];
+                }
+
+                lines
+            })
+        })
+    }
+
+    // Ranges one after the other, each sent anywhere, so numbers may collide like they don't in
+    // the real maps
+    fn any_map() -> impl Strategy<Value = Map> {
+        prop::collection::vec((0..4_usize, 1..8_usize, 0..60_usize), 1..5).prop_map(|ranges| {
+            let mut source = 0;
+            ranges
+                .into_iter()
+                .map(|(gap, length, destination)| {
+                    source += gap + length;
+                    (destination, source - length, length)
+                })
+                .collect()
+        })
+    }
+
+    fn almanac_str(seeds: &[(usize, usize)], maps: &[Map]) -> String {
+        let seeds = seeds
+            .iter()
+            .map(|(start, length)| format!("{start} {length}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut almanac = format!("seeds: {seeds}\n");
+
+        for (i, map) in maps.iter().enumerate() {
+            almanac += &format!("\nmap-{i} map:\n");
+            for (destination, source, length) in map {
+                almanac += &format!("{destination} {source} {length}\n");
+            }
+        }
+
+        almanac
+    }
+
+    fn naive_location(seed: usize, maps: &[Map]) -> usize {
+        maps.iter().fold(seed, |value, map| {
+            map.iter()
+                .find(|(_, source, length)| (*source..source + length).contains(&value))
+                .map_or(value, |(destination, source, _)| {
+                    value - source + destination
+                })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn matches_mapping_every_seed(
+            seeds in prop::collection::vec((0..60_usize, 1..10_usize), 1..4),
+            maps in prop::collection::vec(map(), 1..5),
+        ) {
+            let almanac = almanac_str(&seeds, &maps);
+
+            let single_seeds = seeds.iter().flat_map(|&(start, length)| [start, length]);
+            let expected = single_seeds.map(|seed| naive_location(seed, &maps)).min().unwrap();
+            prop_assert_eq!(expected, part_one(&almanac));
+
+            let all_seeds = seeds.iter().flat_map(|&(start, length)| start..start + length);
+            let expected = all_seeds.map(|seed| naive_location(seed, &maps)).min().unwrap();
+            prop_assert_eq!(expected, part_two(&almanac));
+        }
+
+        #[test]
+        fn matches_mapping_every_seed_with_any_map(
+            seeds in prop::collection::vec((0..60_usize, 1..10_usize), 1..4),
+            maps in prop::collection::vec(any_map(), 1..5),
+        ) {
+            let almanac = almanac_str(&seeds, &maps);
+
+            let all_seeds = seeds.iter().flat_map(|&(start, length)| start..start + length);
+            let expected = all_seeds.map(|seed| naive_location(seed, &maps)).min().unwrap();
+            prop_assert_eq!(expected, part_two(&almanac));
+        }
+    }
+
     #[test]
     fn example() {
         let input = "\
@@ -203,9 +360,20 @@ humidity-to-location map:
 
         assert_eq!(35, part_one(input));
         assert_eq!(46, part_two(input));
+
+        let windows = format!("\u{feff}{}\r\n", input.replace('\n', " \r\n"));
+        assert_eq!(35, part_one(&windows));
+        assert_eq!(46, part_two(&windows));
+    }
+
+    #[test]
+    fn seeds_in_pairs() {
+        assert!(Day05::parse("seeds: 79").is_err());
+        assert!(Day05::parse("seeds: 79 0").is_err());
+        assert!(Day05::parse("seeds:").is_err());
+        assert!(Day05::parse("seeds: 79 1").is_ok());
     }
 
-    #[ignore = "Takes over a minute"]
     #[test]
     fn real() {
         let input = include_str!("../res/day_05.txt");
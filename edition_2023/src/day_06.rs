@@ -1,27 +1,63 @@
 use std::ops::Range;
 
-pub fn part_one() -> usize {
-    // Time:        56     71     79     99
-    // Distance:   334   1135   1350   2430
-    let races = vec![
-        Race::new(56, 334),
-        Race::new(71, 1135),
-        Race::new(79, 1350),
-        Race::new(99, 2430),
-    ];
-
-    races
-        .into_iter()
-        .map(|r| r.winning_range().map(|range| range.len()).unwrap_or(0))
-        .product::<usize>()
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseResult, Required},
+    solution::{or_overflow, Solution},
+};
+
+pub fn part_one(races_str: &str) -> Option<usize> {
+    multiply_ways_to_win(&Race::parse_all(races_str).unwrap(), &mut Explain::off())
+}
+
+pub fn part_two(races_str: &str) -> usize {
+    ways_to_win(&Race::parse_joined(races_str).unwrap(), &mut Explain::off())
+}
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    // Both parts read the same lines differently
+    type Parsed<'a> = (Vec<Race>, Race);
+
+    fn parse(races_str: &str) -> ParseResult<Self::Parsed<'_>> {
+        Ok((Race::parse_all(races_str)?, Race::parse_joined(races_str)?))
+    }
+
+    fn part_one((races, _): &Self::Parsed<'_>, explain: &mut Explain) -> String {
+        or_overflow(multiply_ways_to_win(races, explain))
+    }
+
+    fn part_two((_, race): &Self::Parsed<'_>, explain: &mut Explain) -> Option<String> {
+        Some(ways_to_win(race, explain).to_string())
+    }
+}
+
+fn multiply_ways_to_win(races: &[Race], explain: &mut Explain) -> Option<usize> {
+    races.iter().try_fold(1_usize, |product, race| {
+        product.checked_mul(ways_to_win(race, explain))
+    })
+}
+
+fn ways_to_win(race: &Race, explain: &mut Explain) -> usize {
+    let range = race.winning_range();
+    explain.step("race", || {
+        let (time, distance) = (race.time_limit, race.distance_to_beat);
+        match &range {
+            Some(range) => format!("{time} ms to beat {distance} mm: hold for {range:?} ms"),
+            None => format!("{time} ms to beat {distance} mm: no way to win"),
+        }
+    });
+
+    range.map(|range| range.len()).unwrap_or(0)
 }
 
-pub fn part_two() -> usize {
-    let race = Race::new(56717999, 334113513502430);
-    race.winning_range().unwrap().len()
+fn time_limit(time: &str) -> ParseResult<usize> {
+    Ok(number::<u32>(time)? as usize)
 }
 
-struct Race {
+pub struct Race {
     time_limit: usize,
     distance_to_beat: usize,
 }
@@ -34,35 +70,134 @@ impl Race {
         }
     }
 
+    // Time:      7  15   30
+    // Distance:  9  40  200
+    fn parse_all(races_str: &str) -> ParseResult<Vec<Self>> {
+        let (times, distances) = Self::parse_lines(races_str)?;
+
+        times
+            .split_whitespace()
+            .zip(distances.split_whitespace())
+            .map(|(time, distance)| Ok(Self::new(time_limit(time)?, number(distance)?)))
+            .collect()
+    }
+
+    // The spaces between numbers are a bad kerning, so all digits make a single race
+    fn parse_joined(races_str: &str) -> ParseResult<Self> {
+        let (times, distances) = Self::parse_lines(races_str)?;
+        let join = |numbers: &str| numbers.replace(' ', "");
+
+        Ok(Self::new(
+            time_limit(&join(times))?,
+            number(&join(distances))?,
+        ))
+    }
+
+    fn parse_lines(races_str: &str) -> ParseResult<(&str, &str)> {
+        let mut lines = input::lines(races_str);
+        let times = lines.next().and_then(|line| line.strip_prefix("Time:"));
+        let distances = lines.next().and_then(|line| line.strip_prefix("Distance:"));
+
+        Ok((
+            times.required("the times")?.trim(),
+            distances.required("the distances")?.trim(),
+        ))
+    }
+
+    // Time limits fit in 32 bits, so the distance of any hold time fits too and can be checked
+    // exactly. The roots of the curve only give a first guess, as floats lose the last digits
+    // of big numbers
     fn winning_range(&self) -> Option<Range<usize>> {
+        let time = self.time_limit;
+        let beats = |hold: usize| hold * (time - hold) > self.distance_to_beat;
+
+        // The curve is at its highest halfway
+        let peak = time / 2;
+        if !beats(peak) {
+            return None;
+        }
+
         // x(t) = - t^2 + time_limit * t - distance_to_beat
-        let b = self.time_limit as f64;
+        let b = time as f64;
         // We have to beat the distance, so we add 1
         let c = -((self.distance_to_beat as f64) + 1.0);
+        let delta = (b.powf(2.0) + 4.0 * c).max(0.0).sqrt();
 
-        let delta = b.powf(2.0) + 4.0 * c;
-
-        if delta <= 0.0 {
-            None
-        } else {
-            let delta = delta.sqrt();
-
-            // b is always positive. delta is always smaller then b (smallest distance to beat is 1)
-            let lower_limit = (b - delta) / 2.0;
-            let upper_limit = (b + delta) / 2.0;
+        // We can't be lower than lower, so ceil it
+        // We can't be higher than upper, so floor it
+        let mut lower = (((b - delta) / 2.0).ceil() as usize).min(peak);
+        let mut upper = (((b + delta) / 2.0).floor() as usize).clamp(peak, time);
 
-            // We can't be lower than lower, so ceil it
-            // We can't be higher than upper, so floor it
-            // + 1 cause excluding range
-            Some((lower_limit.ceil() as usize)..(upper_limit.floor() as usize) + 1)
+        while lower > 0 && beats(lower - 1) {
+            lower -= 1;
         }
+        while !beats(lower) {
+            lower += 1;
+        }
+        while upper < time && beats(upper + 1) {
+            upper += 1;
+        }
+        while !beats(upper) {
+            upper -= 1;
+        }
+
+        // + 1 cause excluding range
+        Some(lower..upper + 1)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
+    // Every hold time tried one by one
+    fn naive_ways_to_win(time: usize, distance: usize) -> usize {
+        (0..=time)
+            .filter(|hold| hold * (time - hold) > distance)
+            .count()
+    }
+
+    fn races_str(races: &[(usize, usize)]) -> String {
+        let join = |numbers: Vec<usize>| {
+            numbers
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        format!(
+            "Time: {}\nDistance: {}",
+            join(races.iter().map(|race| race.0).collect()),
+            join(races.iter().map(|race| race.1).collect())
+        )
+    }
+
+    // Distances up to a bit over the best possible one, so some races can't be won
+    fn race(max_time: usize) -> impl Strategy<Value = (usize, usize)> {
+        (1..max_time, any::<usize>())
+            .prop_map(|(time, distance)| (time, distance % (time * time / 4 + 3)))
+    }
+
+    proptest! {
+        #[test]
+        fn matches_trying_every_hold(races in prop::collection::vec(race(80), 1..4)) {
+            let expected = races
+                .iter()
+                .map(|&(time, distance)| naive_ways_to_win(time, distance))
+                .product::<usize>();
+
+            prop_assert_eq!(Some(expected), part_one(&races_str(&races)));
+        }
+
+        #[test]
+        fn matches_trying_every_hold_joined(race in race(100_000)) {
+            prop_assert_eq!(naive_ways_to_win(race.0, race.1), part_two(&races_str(&[race])));
+        }
+    }
+
     #[test]
     fn test_rage_calculation() {
         let race = Race::new(7, 9);
@@ -76,15 +211,57 @@ mod tests {
 
         let race = Race::new(71530, 940200);
         assert_eq!(Some(14..71517), race.winning_range());
+
+        // A single winning hold, at the top of the curve
+        let race = Race::new(2, 0);
+        assert_eq!(Some(1..2), race.winning_range());
+    }
+
+    #[test]
+    fn example() {
+        let races = "\
+        Time:      7  15   30
+        Distance:  9  40  200";
+
+        assert_eq!(Some(288), part_one(races));
+        assert_eq!(71503, part_two(races));
+
+        let mut explain = Explain::on();
+        Day06::part_one(&Day06::parse(races).unwrap(), &mut explain);
+        assert_eq!(
+            "race: 30 ms to beat 200 mm: hold for 11..20 ms",
+            explain.steps()[2].to_string()
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        // 65535 ways to win each race
+        let races = |count| {
+            let time = vec!["65536"; count].join(" ");
+            let distance = vec!["0"; count].join(" ");
+            format!("Time: {time}\nDistance: {distance}")
+        };
+
+        assert_eq!(Some(65535_usize.pow(4)), part_one(&races(4)));
+        assert_eq!(None, part_one(&races(5)));
+
+        assert_eq!(4294967294, part_two("Time: 4294967295\nDistance: 0"));
+        assert!(Day06::parse("Time: 4294967296\nDistance: 0").is_err());
+        assert!(Day06::parse("Time: 65536 65536\nDistance: 0 0").is_err());
     }
 
     #[test]
     fn part_one_test() {
-        assert_eq!(211904, part_one());
+        let races = include_str!("../res/day_06.txt");
+
+        assert_eq!(Some(211904), part_one(races));
     }
 
     #[test]
     fn part_two_test() {
-        assert_eq!(43364472, part_two());
+        let races = include_str!("../res/day_06.txt");
+
+        assert_eq!(43364472, part_two(races));
     }
 }
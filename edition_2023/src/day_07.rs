@@ -1,135 +1,290 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead};
 
-pub fn solve_puzzle(hands_str: &str, jokers: bool) -> usize {
-    let mut hands = CamelHand::parse_all(hands_str, jokers);
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseError, ParseResult, Required},
+    solution::{or_overflow, Solution},
+    stream::try_fold_lines,
+};
+
+pub fn part_one(hands_str: &str) -> Option<usize> {
+    solve_puzzle(hands_str, &HandRules::camel())
+}
+
+pub fn part_two(hands_str: &str) -> Option<usize> {
+    solve_puzzle(hands_str, &HandRules::camel_with_jokers())
+}
+
+pub fn part_one_buffered(reader: impl BufRead) -> io::Result<Option<usize>> {
+    solve_buffered(reader, &HandRules::camel())
+}
+
+pub fn part_two_buffered(reader: impl BufRead) -> io::Result<Option<usize>> {
+    solve_buffered(reader, &HandRules::camel_with_jokers())
+}
+
+pub fn solve_puzzle(hands_str: &str, rules: &HandRules) -> Option<usize> {
+    total_winnings(&CamelHand::parse_all(hands_str, rules).unwrap())
+}
+
+// Only the parsed hands are kept, as ranking needs all of them
+pub fn solve_buffered(reader: impl BufRead, rules: &HandRules) -> io::Result<Option<usize>> {
+    let hands = try_fold_lines(reader, Vec::new(), |mut hands, line| {
+        hands.push(CamelHand::parse(line, rules)?);
+        Ok(hands)
+    })?;
+
+    Ok(total_winnings(&hands))
+}
+
+pub struct Day07;
+
+impl Solution for Day07 {
+    // The hands ranked by each part's rules
+    type Parsed<'a> = (Vec<CamelHand>, Vec<CamelHand>);
+
+    fn parse(hands_str: &str) -> ParseResult<Self::Parsed<'_>> {
+        Ok((
+            CamelHand::parse_all(hands_str, &HandRules::camel())?,
+            CamelHand::parse_all(hands_str, &HandRules::camel_with_jokers())?,
+        ))
+    }
+
+    fn part_one((hands, _): &Self::Parsed<'_>, _: &mut Explain) -> String {
+        or_overflow(total_winnings(hands))
+    }
+
+    fn part_two((_, hands): &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(or_overflow(total_winnings(hands)))
+    }
+}
+
+// None when the winnings don't fit
+fn total_winnings(hands: &[CamelHand]) -> Option<usize> {
+    let mut hands = hands.iter().collect::<Vec<_>>();
     hands.sort();
 
     hands
         .into_iter()
-        .rev()
         .enumerate()
-        .map(|(i, hand)| (i + 1) * hand.bet)
-        .sum()
-}
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
-enum CamelCard {
-    Ace,
-    King,
-    Queen,
-    Jockey,
-    Ten,
-    Nine,
-    Eight,
-    Seven,
-    Six,
-    Five,
-    Four,
-    Three,
-    Two,
-    Joker,
-}
-
-impl CamelCard {
-    fn parse_cards(cards: &str, jokers: bool) -> Vec<Self> {
-        cards
-            .chars()
-            .map(|card_char| match &card_char {
-                'A' => Self::Ace,
-                'K' => Self::King,
-                'Q' => Self::Queen,
-                'J' => {
-                    if jokers {
-                        Self::Joker
-                    } else {
-                        Self::Jockey
-                    }
-                }
-                'T' => Self::Ten,
-                '9' => Self::Nine,
-                '8' => Self::Eight,
-                '7' => Self::Seven,
-                '6' => Self::Six,
-                '5' => Self::Five,
-                '4' => Self::Four,
-                '3' => Self::Three,
-                '2' => Self::Two,
-                _ => panic!("No card for char {}", card_char),
-            })
-            .collect()
+        .try_fold(0_usize, |total, (i, hand)| {
+            total.checked_add((i + 1).checked_mul(hand.bet)?)
+        })
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tiebreak {
+    // Camel Cards: the first card that differs in the order they were dealt decides
+    DealtOrder,
+    // Poker: cards are compared from the biggest group to the smallest, stronger cards first
+    Grouped,
+}
+
+#[derive(Debug, Clone)]
+pub struct HandRules {
+    // Strongest card first
+    pub order: Vec<char>,
+    pub wildcards: HashSet<char>,
+    pub tiebreak: Tiebreak,
+}
+
+impl HandRules {
+    pub fn new(order: &str, wildcards: &str, tiebreak: Tiebreak) -> Self {
+        Self {
+            order: order.chars().collect(),
+            wildcards: wildcards.chars().collect(),
+            tiebreak,
+        }
+    }
+
+    pub fn camel() -> Self {
+        Self::new("AKQJT98765432", "", Tiebreak::DealtOrder)
+    }
+
+    pub fn camel_with_jokers() -> Self {
+        Self::new("AKQT98765432J", "J", Tiebreak::DealtOrder)
+    }
+
+    fn strength(&self, card: char) -> usize {
+        let position = self
+            .order
+            .iter()
+            .position(|c| *c == card)
+            .unwrap_or_else(|| panic!("No card for char {}", card));
+
+        self.order.len() - position
+    }
+
+    fn is_wild(&self, card: char) -> bool {
+        self.wildcards.contains(&card)
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum CamelHandType {
-    FiveOfAKind,
-    FourOfAKind,
-    FullHouse,
-    ThreeOfAKind,
-    TwoPair,
-    OnePair,
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum CamelHandType {
     HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+
+impl CamelHandType {
+    // Group sizes must be sorted from biggest to smallest
+    fn classify(groups: &[usize]) -> Self {
+        match (groups[0], groups.get(1).unwrap_or(&0_usize)) {
+            (5.., _) => Self::FiveOfAKind,
+            (4, _) => Self::FourOfAKind,
+            (3, 2..) => Self::FullHouse,
+            (3, _) => Self::ThreeOfAKind,
+            (2, 2) => Self::TwoPair,
+            (2, _) => Self::OnePair,
+            (_, _) => Self::HighCard,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct CamelHand {
-    cards: Vec<CamelCard>,
-    bet: usize,
-    kind: CamelHandType,
+pub struct CamelHand {
+    pub cards: Vec<char>,
+    pub bet: usize,
+    pub kind: CamelHandType,
+    // Card every wildcard stands in for
+    pub substitute: Option<char>,
+    // Group sizes after substitution, biggest first. Ranks hands of any size
+    groups: Vec<usize>,
+    // (strength, card) pairs compared when the groups are the same
+    tiebreak: Vec<(usize, char)>,
 }
 
 impl CamelHand {
-    fn parse_all(hands: &str, jokers: bool) -> Vec<Self> {
-        hands
-            .lines()
-            .map(|hand| Self::parse(hand.trim(), jokers))
+    pub fn parse_all(hands: &str, rules: &HandRules) -> ParseResult<Vec<Self>> {
+        input::lines(hands)
+            .map(|hand| Self::parse(hand, rules))
             .collect()
     }
 
-    fn parse(hand_and_bet: &str, jokers: bool) -> Self {
-        let parts: Vec<&str> = hand_and_bet.split_whitespace().collect();
+    pub fn parse(hand_and_bet: &str, rules: &HandRules) -> ParseResult<Self> {
+        let mut parts = hand_and_bet.split_whitespace();
+        let cards = parts.next().required("the cards")?;
+        let bet = number(parts.next().required("the bet")?)?;
 
-        let cards = CamelCard::parse_cards(parts[0], jokers);
-        let bet: usize = parts[1].parse().unwrap();
+        if let Some(card) = cards.chars().find(|card| !rules.order.contains(card)) {
+            return Err(ParseError::new(format!("No card for char {card}")));
+        }
+
+        Ok(Self::new(cards, bet, rules))
+    }
 
-        let cards_clone = cards.clone();
+    pub fn new(cards: &str, bet: usize, rules: &HandRules) -> Self {
+        let cards = cards.chars().collect::<Vec<_>>();
         let mut counts = HashMap::new();
 
-        for card in cards_clone.into_iter() {
+        for card in cards.iter().filter(|card| !rules.is_wild(**card)) {
             counts
-                .entry(card)
+                .entry(*card)
                 .and_modify(|counter| *counter += 1)
                 .or_insert(1);
         }
 
-        let jokers = if counts.len() < 2 {
-            None
-        } else {
-            counts.remove(&CamelCard::Joker)
-        };
+        // Adding every wildcard to the biggest group is always the best play
+        let substitute = counts
+            .iter()
+            .max_by_key(|(card, count)| (**count, rules.strength(**card)))
+            .map(|(card, _)| *card)
+            .or_else(|| rules.order.iter().find(|c| !rules.is_wild(**c)).copied());
+
+        let best = cards
+            .iter()
+            .map(|card| match substitute {
+                Some(substitute) if rules.is_wild(*card) => substitute,
+                _ => *card,
+            })
+            .collect::<Vec<_>>();
+
+        let mut best_counts = HashMap::new();
+        for card in &best {
+            *best_counts.entry(*card).or_insert(0) += 1;
+        }
+
+        let mut groups = best_counts.values().copied().collect::<Vec<usize>>();
+        groups.sort();
+        groups.reverse();
 
-        let mut counts = counts.into_values().collect::<Vec<_>>();
+        let kind = CamelHandType::classify(&groups);
 
-        counts.sort();
-        counts.reverse();
+        let tiebreak = match rules.tiebreak {
+            Tiebreak::DealtOrder => cards
+                .iter()
+                .map(|card| (rules.strength(*card), *card))
+                .collect(),
+            Tiebreak::Grouped => {
+                let mut ranked = best
+                    .iter()
+                    .map(|card| (rules.strength(*card), *card))
+                    .collect::<Vec<_>>();
+                ranked.sort_by_key(|(strength, card)| (best_counts[card], *strength));
+                ranked.reverse();
+                ranked
+            }
+        };
 
-        if let Some(j) = jokers {
-            let old = counts[0];
-            let _ = std::mem::replace(&mut counts[0], old + j);
+        Self {
+            cards,
+            bet,
+            kind,
+            substitute: substitute.filter(|_| counts.values().sum::<usize>() < best.len()),
+            groups,
+            tiebreak,
         }
+    }
+
+    // The cards this hand plays as once wildcards are replaced
+    pub fn best_substitution(&self, rules: &HandRules) -> String {
+        self.cards
+            .iter()
+            .map(|card| match self.substitute {
+                Some(substitute) if rules.is_wild(*card) => substitute,
+                _ => *card,
+            })
+            .collect()
+    }
 
-        let kind = match (counts[0], counts.get(1).unwrap_or(&0_usize)) {
-            (5, _) => CamelHandType::FiveOfAKind,
-            (4, _) => CamelHandType::FourOfAKind,
-            (3, 2) => CamelHandType::FullHouse,
-            (3, _) => CamelHandType::ThreeOfAKind,
-            (2, 2) => CamelHandType::TwoPair,
-            (2, _) => CamelHandType::OnePair,
-            (_, _) => CamelHandType::HighCard,
+    pub fn explain(&self, other: &Self) -> String {
+        let this = self.cards.iter().collect::<String>();
+        let that = other.cards.iter().collect::<String>();
+
+        let verb = match self.cmp(other) {
+            Ordering::Less => "loses to",
+            Ordering::Equal => return format!("{this} ties with {that}"),
+            Ordering::Greater => "beats",
         };
 
-        Self { cards, bet, kind }
+        if self.groups != other.groups {
+            return format!(
+                "{this} ({:?}) {verb} {that} ({:?}) by hand type",
+                self.kind, other.kind
+            );
+        }
+
+        let (position, ((_, this_card), (_, that_card))) = self
+            .tiebreak
+            .iter()
+            .zip(other.tiebreak.iter())
+            .enumerate()
+            .find(|(_, (a, b))| a != b)
+            .expect("Different hands with the same groups must differ in a card");
+
+        format!(
+            "{this} {verb} {that}: both {:?}, tiebreak card {} is {this_card} against {that_card}",
+            self.kind,
+            position + 1
+        )
     }
 }
 
@@ -141,19 +296,9 @@ impl PartialOrd for CamelHand {
 
 impl Ord for CamelHand {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.kind.cmp(&other.kind) {
-            Ordering::Equal => {
-                for (this_card, other_card) in self.cards.iter().zip(other.cards.iter()) {
-                    match this_card.cmp(other_card) {
-                        Ordering::Equal => (),
-                        different => return different,
-                    };
-                }
-
-                Ordering::Equal
-            }
-            different => different,
-        }
+        self.groups
+            .cmp(&other.groups)
+            .then_with(|| self.tiebreak.cmp(&other.tiebreak))
     }
 }
 
@@ -161,6 +306,66 @@ impl Ord for CamelHand {
 mod tests {
     use super::*;
 
+    fn multisets(cards: &[char], size: usize) -> Vec<String> {
+        if size == 0 {
+            return vec![String::new()];
+        }
+
+        (0..cards.len())
+            .flat_map(|i| {
+                multisets(&cards[i..], size - 1)
+                    .into_iter()
+                    .map(move |rest| format!("{}{rest}", cards[i]))
+            })
+            .collect()
+    }
+
+    // Every way of replacing each wildcard by any other card
+    fn substitutions(hand: &str, rules: &HandRules) -> Vec<String> {
+        hand.chars().fold(vec![String::new()], |partials, card| {
+            let options = if rules.is_wild(card) {
+                rules
+                    .order
+                    .iter()
+                    .copied()
+                    .filter(|c| !rules.is_wild(*c))
+                    .collect()
+            } else {
+                vec![card]
+            };
+
+            partials
+                .iter()
+                .flat_map(|partial| options.iter().map(move |c| format!("{partial}{c}")))
+                .collect()
+        })
+    }
+
+    fn brute_force_kind(hand: &str) -> CamelHandType {
+        let mut counts = HashMap::new();
+        for card in hand.chars() {
+            *counts.entry(card).or_insert(0) += 1;
+        }
+        let has = |n: usize| counts.values().any(|c| *c == n);
+        let pairs = counts.values().filter(|c| **c == 2).count();
+
+        if has(5) {
+            CamelHandType::FiveOfAKind
+        } else if has(4) {
+            CamelHandType::FourOfAKind
+        } else if has(3) && has(2) {
+            CamelHandType::FullHouse
+        } else if has(3) {
+            CamelHandType::ThreeOfAKind
+        } else if pairs == 2 {
+            CamelHandType::TwoPair
+        } else if pairs == 1 {
+            CamelHandType::OnePair
+        } else {
+            CamelHandType::HighCard
+        }
+    }
+
     #[test]
     fn example() {
         let hands_str = "\
@@ -170,15 +375,169 @@ mod tests {
         KTJJT 220
         QQQJA 483";
 
-        assert_eq!(6440, solve_puzzle(hands_str, false));
-        assert_eq!(5905, solve_puzzle(hands_str, true));
+        assert_eq!(Some(6440), part_one(hands_str));
+        assert_eq!(Some(5905), part_two(hands_str));
+    }
+
+    #[test]
+    fn overflow() {
+        // The stronger hand is ranked second, which doubles its bet
+        let bet = usize::MAX / 2;
+        let hands_str = format!("32T3K 1\nKK677 {bet}");
+
+        assert_eq!(Some(usize::MAX), part_one(&hands_str));
+        assert_eq!(None, part_one(&format!("32T3K 2\nKK677 {bet}")));
+        assert_eq!(None, part_one(&format!("32T3K {bet}\nKK677 {}", bet + 1)));
+        assert_eq!(
+            None,
+            part_two_buffered(format!("32T3K 2\nKK677 {bet}").as_bytes()).unwrap()
+        );
+        assert_eq!(
+            "overflow",
+            Day07::part_one(
+                &Day07::parse(&format!("32T3K 2\nKK677 {bet}")).unwrap(),
+                &mut Explain::off()
+            )
+        );
+    }
+
+    #[test]
+    fn jokers() {
+        let rules = HandRules::camel_with_jokers();
+
+        let hand = CamelHand::new("KTJJT", 0, &rules);
+        assert_eq!(CamelHandType::FourOfAKind, hand.kind);
+        assert_eq!(Some('T'), hand.substitute);
+        assert_eq!("KTTTT", hand.best_substitution(&rules));
+
+        let hand = CamelHand::new("JJJJJ", 0, &rules);
+        assert_eq!(CamelHandType::FiveOfAKind, hand.kind);
+        assert_eq!("AAAAA", hand.best_substitution(&rules));
+
+        let hand = CamelHand::new("2345A", 0, &rules);
+        assert_eq!(None, hand.substitute);
+        assert_eq!("2345A", hand.best_substitution(&rules));
+    }
+
+    #[test]
+    fn explanations() {
+        let rules = HandRules::camel_with_jokers();
+        let hand = |cards| CamelHand::new(cards, 0, &rules);
+
+        assert_eq!(
+            "T55J5 loses to QQQJA: both FourOfAKind, tiebreak card 1 is T against Q",
+            hand("T55J5").explain(&hand("QQQJA"))
+        );
+        assert_eq!(
+            "KTJJT (FourOfAKind) beats KK677 (TwoPair) by hand type",
+            hand("KTJJT").explain(&hand("KK677"))
+        );
+        assert_eq!(
+            "JKKK2 loses to QQQQ2: both FourOfAKind, tiebreak card 1 is J against Q",
+            hand("JKKK2").explain(&hand("QQQQ2"))
+        );
+        assert_eq!(
+            "32T3K ties with 32T3K",
+            hand("32T3K").explain(&hand("32T3K"))
+        );
+    }
+
+    #[test]
+    fn grouped_tiebreak() {
+        let rules = HandRules::new("AKQJT98765432", "", Tiebreak::Grouped);
+        let hand = |cards| CamelHand::new(cards, 0, &rules);
+
+        // Poker ranks the pair before the kickers, whatever the dealt order
+        assert!(hand("A3342") < hand("K4432"));
+        assert!(hand("AQ2KJ") < hand("KAQJ3"));
+        assert_eq!(Ordering::Equal, hand("K3K3A").cmp(&hand("3AKK3")));
+    }
+
+    #[test]
+    fn every_five_card_hand() {
+        let plain = HandRules::camel();
+        let hands = multisets(&plain.order, 5);
+        assert_eq!(6188, hands.len());
+
+        for cards in &hands {
+            let hand = CamelHand::new(cards, 0, &plain);
+            assert_eq!(brute_force_kind(cards), hand.kind, "{cards}");
+            assert_eq!(None, hand.substitute);
+        }
+
+        let jokers = HandRules::camel_with_jokers();
+
+        for cards in &hands {
+            let hand = CamelHand::new(cards, 0, &jokers);
+            let best = substitutions(cards, &jokers)
+                .iter()
+                .map(|s| brute_force_kind(s))
+                .max()
+                .unwrap();
+
+            assert_eq!(best, hand.kind, "{cards}");
+            assert_eq!(
+                best,
+                brute_force_kind(&hand.best_substitution(&jokers)),
+                "{cards}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_five_card_hand_is_ordered_by_kind() {
+        let rules = HandRules::camel_with_jokers();
+        let mut hands = multisets(&rules.order, 5)
+            .iter()
+            .map(|cards| CamelHand::new(cards, 0, &rules))
+            .collect::<Vec<_>>();
+
+        hands.sort();
+
+        assert!(hands.windows(2).all(|pair| pair[0].kind <= pair[1].kind));
+        assert!(hands.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn other_hand_sizes() {
+        let rules = HandRules::camel_with_jokers();
+
+        for size in 1..=4 {
+            for cards in multisets(&rules.order, size) {
+                let hand = CamelHand::new(&cards, 0, &rules);
+                let best = substitutions(&cards, &rules)
+                    .iter()
+                    .map(|s| CamelHand::new(s, 0, &rules).groups)
+                    .max()
+                    .unwrap();
+
+                assert_eq!(best, hand.groups, "{cards}");
+                assert_eq!(size, hand.groups.iter().sum::<usize>());
+            }
+        }
+
+        let hand = |cards| CamelHand::new(cards, 0, &rules);
+
+        assert_eq!(CamelHandType::ThreeOfAKind, hand("KJK").kind);
+        assert_eq!(CamelHandType::FiveOfAKind, hand("QQQQJQ").kind);
+        assert_eq!(CamelHandType::FullHouse, hand("22233A").kind);
+        assert!(hand("222333") < hand("2222AK"));
+        assert!(hand("222333") > hand("223344"));
     }
 
     #[test]
     fn real() {
         let hands_str = include_str!("../res/day_07.txt");
 
-        assert_eq!(251545216, solve_puzzle(hands_str, false));
-        assert_eq!(250384185, solve_puzzle(hands_str, true));
+        assert_eq!(Some(251545216), part_one(hands_str));
+        assert_eq!(Some(250384185), part_two(hands_str));
+        assert_eq!(
+            Some(251545216),
+            part_one_buffered(hands_str.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            Some(250384185),
+            part_two_buffered(hands_str.as_bytes()).unwrap()
+        );
     }
 }
@@ -1,21 +1,202 @@
-use num::integer::lcm;
-use std::collections::HashMap;
+use num::integer::{ExtendedGcd, Integer};
+use std::{collections::HashMap, fmt, iter};
+
+use crate::common::{
+    cycle::{find_cycle, Cycle},
+    explain::Explain,
+    input,
+    parse::{ParseError, ParseResult, Required},
+    solution::Solution,
+};
+
+pub fn part_one(map_str: &str) -> Option<usize> {
+    walk_from_aaa(&GhostMap::parse_map(map_str).unwrap(), &mut Explain::off())
+}
+
+pub fn part_two(map_str: &str) -> Option<usize> {
+    walk_ghosts(&GhostMap::parse_map(map_str).unwrap(), &mut Explain::off())
+}
+
+pub struct Day08;
 
-pub fn part_one(map_str: &str) -> usize {
-    let map = GhostMap::parse_map(map_str);
+impl Solution for Day08 {
+    type Parsed<'a> = GhostMap<'a>;
 
-    map.find_node(0, map.find_aaa_index(), |node| node.name == "ZZZ")
+    fn parse(map_str: &str) -> ParseResult<Self::Parsed<'_>> {
+        GhostMap::parse_map(map_str)
+    }
+
+    fn part_one(map: &Self::Parsed<'_>, explain: &mut Explain) -> String {
+        steps_or_never(walk_from_aaa(map, explain))
+    }
+
+    fn part_two(map: &Self::Parsed<'_>, explain: &mut Explain) -> Option<String> {
+        Some(steps_or_never(walk_ghosts(map, explain)))
+    }
+}
+
+fn steps_or_never(steps: Option<usize>) -> String {
+    steps.map_or_else(|| String::from("never"), |steps| steps.to_string())
 }
 
-pub fn part_two(map_str: &str) -> usize {
-    let map = GhostMap::parse_map(map_str);
+fn walk_from_aaa(map: &GhostMap, explain: &mut Explain) -> Option<usize> {
+    // Maps made for the ghosts may have no AAA, and then there is no way to ZZZ either
+    let Some(aaa) = map.find_aaa_index() else {
+        explain.step("walk", || String::from("no AAA node"));
+        return None;
+    };
+
+    let walk = map.walk(aaa, |node| node.name == "ZZZ");
+    explain.step("walk", || format!("AAA: {walk}"));
+
+    first_meeting(&[walk])
+}
 
-    map.nodes
+fn walk_ghosts(map: &GhostMap, explain: &mut Explain) -> Option<usize> {
+    let walks = map
+        .nodes
         .iter()
         .filter(|n| n.name.ends_with('A'))
-        .map(|n| map.find_node(0, n.id, |node| node.name.ends_with('Z')))
-        .reduce(lcm)
-        .unwrap()
+        .map(|n| {
+            let walk = map.walk(n.id, |node| node.name.ends_with('Z'));
+            explain.step("ghost", || format!("{}: {walk}", n.name));
+            walk
+        })
+        .collect::<Vec<_>>();
+
+    first_meeting(&walks)
+}
+
+// Where a walk from one node ends up: after `tail` steps it repeats itself every `cycle` steps.
+// `hits` are the steps, up to the end of the first cycle, at which it stands on an end node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    pub tail: usize,
+    pub cycle: usize,
+    pub hits: Vec<usize>,
+}
+
+impl Walk {
+    pub fn hits_at(&self, steps: usize) -> bool {
+        let steps = if steps < self.tail {
+            steps
+        } else {
+            self.tail + (steps - self.tail) % self.cycle
+        };
+
+        self.hits.binary_search(&steps).is_ok()
+    }
+}
+
+impl fmt::Display for Walk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tail {}, cycle {}, end nodes at {:?}",
+            self.tail, self.cycle, self.hits
+        )
+    }
+}
+
+// The first step at which every walk stands on an end node, if there is one. Nothing is assumed
+// about the walks: the steps until they have all gone once round their longest cycle are checked
+// one by one, which catches walks that are on end nodes most of the time. After that each walk
+// needs the steps to be one of its hits modulo its cycle. The Chinese Remainder Theorem solves
+// that for the walks with the fewest hits, as long as their combinations stay few, and the steps
+// it leaves are tried in order against the others
+pub fn first_meeting(walks: &[Walk]) -> Option<usize> {
+    let looping = walks.iter().map(|walk| walk.tail).max()?;
+    let checked = looping + walks.iter().map(|walk| walk.cycle).max()?;
+
+    if let Some(steps) = (0..checked).find(|&steps| walks.iter().all(|walk| walk.hits_at(steps))) {
+        return Some(steps);
+    }
+
+    let residues = |walk: &Walk| {
+        let cycle = walk.cycle as i128;
+        walk.hits
+            .iter()
+            .filter(|&&hit| hit >= walk.tail)
+            .map(|&hit| hit as i128 % cycle)
+            .collect::<Vec<_>>()
+    };
+
+    let mut walks = walks
+        .iter()
+        .map(|walk| (walk, residues(walk)))
+        .collect::<Vec<_>>();
+    walks.sort_by_key(|(_, residues)| residues.len());
+
+    // Merged congruences all share the LCM of the merged cycles as their modulus
+    let mut congruences = vec![(0_i128, 1_i128)];
+    let mut merged = 0;
+
+    for (walk, residues) in &walks {
+        if congruences.len() * residues.len() > MAX_CONGRUENCES {
+            break;
+        }
+
+        let cycle = walk.cycle as i128;
+        congruences = congruences
+            .iter()
+            .flat_map(|&congruence| {
+                residues
+                    .iter()
+                    .filter_map(move |&residue| merge(congruence, (residue, cycle)))
+            })
+            .collect();
+        congruences.sort_unstable();
+        congruences.dedup();
+        merged += 1;
+    }
+
+    let &(_, modulus) = congruences.first()?;
+    let others = &walks[merged..];
+
+    // Every walk is back where it was after the LCM of all the cycles, so if no step up to then
+    // works none ever will
+    let period = walks.iter().try_fold(1_usize, |period, (walk, _)| {
+        (period / period.gcd(&walk.cycle)).checked_mul(walk.cycle)
+    });
+    let end = period.and_then(|period| checked.checked_add(period));
+
+    // Past a usize the meeting would not fit anyway
+    let Ok(modulus) = usize::try_from(modulus) else {
+        return congruences
+            .iter()
+            .filter_map(|&(residue, _)| usize::try_from(residue).ok())
+            .filter(|&steps| steps >= checked)
+            .find(|&steps| others.iter().all(|(walk, _)| walk.hits_at(steps)));
+    };
+
+    (checked / modulus..)
+        .map_while(|k| k.checked_mul(modulus))
+        .flat_map(|base| {
+            congruences
+                .iter()
+                .filter_map(move |&(residue, _)| base.checked_add(residue as usize))
+        })
+        .skip_while(|&steps| steps < checked)
+        .take_while(|&steps| end.is_none_or(|end| steps < end))
+        .find(|&steps| others.iter().all(|(walk, _)| walk.hits_at(steps)))
+}
+
+// Beyond this many combinations of hits the steps are tried one by one instead
+const MAX_CONGRUENCES: usize = 1 << 12;
+
+// The steps that are `a` modulo `m` and `b` modulo `n`, as one residue modulo lcm(m, n)
+fn merge((a, m): (i128, i128), (b, n): (i128, i128)) -> Option<(i128, i128)> {
+    let ExtendedGcd { gcd, x, .. } = m.extended_gcd(&n);
+
+    if (b - a) % gcd != 0 {
+        return None;
+    }
+
+    // Past an i128 the meeting would not fit a usize anyway
+    let lcm = (m / gcd).checked_mul(n)?;
+    let k = ((b - a) / gcd % (n / gcd) * x).rem_euclid(n / gcd);
+
+    Some(((a + m.checked_mul(k)?).rem_euclid(lcm), lcm))
 }
 
 #[derive(Debug)]
@@ -25,110 +206,279 @@ enum Command {
 }
 
 impl Command {
-    fn parse(command_str: char) -> Self {
+    fn parse(command_str: char) -> ParseResult<Self> {
         match command_str {
-            'L' => Self::Left,
-            'R' => Self::Right,
-            _ => panic!("Invalid command {}", command_str),
+            'L' => Ok(Self::Left),
+            'R' => Ok(Self::Right),
+            _ => Err(ParseError::new(format!("Invalid command {command_str}"))),
         }
     }
 }
 
 #[derive(Debug)]
-struct GhostMap<'a> {
+pub struct GhostMap<'a> {
     commands: Vec<Command>,
     nodes: Vec<Node<'a>>,
     cache: HashMap<String, usize>,
 }
 
 impl<'a> GhostMap<'a> {
-    fn parse_map(map_str: &'a str) -> Self {
-        let mut lines = map_str.lines();
-        let first_line = lines.next().unwrap();
-
-        let commands = first_line.chars().map(Command::parse).collect::<Vec<_>>();
-        lines.next();
+    fn parse_map(map_str: &'a str) -> ParseResult<Self> {
+        let sections = input::sections(map_str);
+        let [commands, nodes] = sections.as_slice() else {
+            return Err(ParseError::new(
+                "Expected the commands and the nodes separated by a blank line",
+            ));
+        };
+
+        let commands = commands
+            .iter()
+            .flat_map(|line| line.chars())
+            .map(Command::parse)
+            .collect::<ParseResult<Vec<_>>>()?;
+
+        if commands.is_empty() {
+            return Err(ParseError::new("Expected at least one command"));
+        }
 
         let mut cache = HashMap::new();
 
-        let nodes = lines
+        let nodes = nodes
+            .iter()
             .enumerate()
             .map(|(id, line)| {
-                let node = Node::parse(line, id);
+                let node = Node::parse(line, id)?;
                 cache.entry(node.name.to_owned()).or_insert(node.id);
-                node
+                Ok(node)
             })
-            .collect::<Vec<_>>();
+            .collect::<ParseResult<Vec<_>>>()?;
+
+        // Walking trusts every node to lead somewhere
+        if let Some(missing) = nodes
+            .iter()
+            .flat_map(|node| [node.left, node.right])
+            .find(|next| !cache.contains_key(*next))
+        {
+            return Err(ParseError::new(format!("Unknown node {missing}")));
+        }
 
-        Self {
+        Ok(Self {
             commands,
             nodes,
             cache,
-        }
+        })
     }
 
-    fn find_aaa_index(&self) -> usize {
-        *self.cache.get("AAA").unwrap()
+    fn find_aaa_index(&self) -> Option<usize> {
+        self.cache.get("AAA").copied()
     }
 
-    fn find_node(
-        &self,
-        iteration: usize,
-        start_index: usize,
-        check_finished: impl Fn(&Node<'a>) -> bool,
-    ) -> usize {
-        let mut current_node = &self.nodes[start_index];
-        let mut next_index = start_index;
-
-        for command in &self.commands {
-            let next = match command {
-                Command::Left => current_node.left,
-                Command::Right => current_node.right,
+    // Follows the commands one step at a time until a (node, command) state comes round again
+    pub fn walk(&self, start: usize, is_end: impl Fn(&Node<'a>) -> bool) -> Walk {
+        let step = |&(node, index): &(usize, usize)| {
+            let current = &self.nodes[node];
+            let next = match self.commands[index] {
+                Command::Left => current.left,
+                Command::Right => current.right,
             };
 
-            next_index = *self.cache.get(next).unwrap();
-            current_node = &self.nodes[next_index];
-        }
+            (self.cache[next], (index + 1) % self.commands.len())
+        };
 
-        let iteration = iteration + 1;
+        let Cycle { mu, lambda } = find_cycle((start, 0), step);
 
-        if check_finished(current_node) {
-            iteration * self.commands.len()
-        } else {
-            self.find_node(iteration, next_index, check_finished)
+        let hits = iter::successors(Some((start, 0)), |state| Some(step(state)))
+            .take(mu + lambda)
+            .enumerate()
+            .filter(|(_, (node, _))| is_end(&self.nodes[*node]))
+            .map(|(steps, _)| steps)
+            .collect();
+
+        Walk {
+            tail: mu,
+            cycle: lambda,
+            hits,
         }
     }
 }
 
 #[derive(Debug, Clone)]
-struct Node<'a> {
-    name: &'a str,
-    id: usize,
+pub struct Node<'a> {
+    pub name: &'a str,
+    pub id: usize,
     left: &'a str,
     right: &'a str,
 }
 
 impl<'a> Node<'a> {
-    fn parse(line: &'a str, id: usize) -> Self {
-        let words = line.split_whitespace().collect::<Vec<_>>();
-
-        let name = words[0];
-        let left = &words[2][1..words[2].len() - 1];
-        let right = &words[3][0..words[3].len() - 1];
-
-        Self {
+    // AAA = (BBB, CCC)
+    fn parse(line: &'a str, id: usize) -> ParseResult<Self> {
+        let (name, next) = line
+            .split_once(" = ")
+            .required("the ' = ' after the node")?;
+        let (left, right) = next
+            .strip_prefix('(')
+            .and_then(|next| next.strip_suffix(')'))
+            .and_then(|next| next.split_once(", "))
+            .required("the next nodes like (BBB, CCC)")?;
+
+        Ok(Self {
             name,
             id,
             left,
             right,
-        }
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
+    // Each ghost follows a path of a few nodes from its start into a loop made of whole passes
+    // over the commands, so the tail puts its loop at any offset. Any node after the start may
+    // be an end node, several in one loop or none at all. The side not taken at each node points
+    // anywhere along the ghost's path
+    fn ghost_map() -> impl Strategy<Value = (String, Vec<(String, String, String)>)> {
+        let ghost = (
+            0..=3_usize,
+            1..=3_usize,
+            prop::collection::vec(any::<bool>(), 16),
+            prop::collection::vec(any::<usize>(), 16),
+        );
+
+        (
+            prop::collection::vec(any::<bool>(), 1..=4),
+            prop::collection::vec(ghost, 1..=3),
+        )
+            .prop_map(|(commands, ghosts)| {
+                let mut nodes = Vec::new();
+
+                for (g, (tail, passes, ends, others)) in ghosts.into_iter().enumerate() {
+                    let letter = (b'A' + g as u8) as char;
+                    let path = 1 + tail + passes * commands.len();
+
+                    // The first end node of the ghost starting at AAA is ZZZ
+                    let mut names = vec![format!("{letter}{letter}A")];
+                    for (k, &end) in ends.iter().enumerate().take(path).skip(1) {
+                        names.push(match (end, g) {
+                            (true, 0) if !names.iter().any(|name| name == "ZZZ") => {
+                                "ZZZ".to_string()
+                            }
+                            (true, _) => format!("{letter}{k:02}Z"),
+                            (false, _) => format!("{letter}{k:02}"),
+                        });
+                    }
+
+                    for (k, name) in names.iter().enumerate() {
+                        let taken = names[if k + 1 == path { 1 + tail } else { k + 1 }].clone();
+                        let other = names[others[k] % path].clone();
+
+                        nodes.push(if commands[k % commands.len()] {
+                            (name.clone(), taken, other)
+                        } else {
+                            (name.clone(), other, taken)
+                        });
+                    }
+                }
+
+                let commands = commands
+                    .iter()
+                    .map(|&left| if left { 'L' } else { 'R' })
+                    .collect();
+
+                (commands, nodes)
+            })
+    }
+
+    // Any map at all: a handful of nodes pointing anywhere, the first a start and the others
+    // starts, ends or neither
+    fn any_map() -> impl Strategy<Value = (String, Vec<(String, String, String)>)> {
+        (
+            "[LR]{1,5}",
+            prop::collection::vec((0..3_usize, any::<usize>(), any::<usize>()), 1..=6),
+        )
+            .prop_map(|(commands, kinds)| {
+                let names = kinds
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (kind, _, _))| {
+                        format!("{i}{}", ["A", "Z", "X"][if i == 0 { 0 } else { *kind }])
+                    })
+                    .collect::<Vec<_>>();
+                let nodes = kinds
+                    .iter()
+                    .zip(&names)
+                    .map(|((_, left, right), name)| {
+                        let next = |k: &usize| names[k % names.len()].clone();
+                        (name.clone(), next(left), next(right))
+                    })
+                    .collect();
+
+                (commands, nodes)
+            })
+    }
+
+    fn to_map(commands: &str, nodes: &[(String, String, String)]) -> String {
+        nodes
+            .iter()
+            .fold(format!("{commands}\n\n"), |map, (name, left, right)| {
+                map + &format!("{name} = ({left}, {right})\n")
+            })
+    }
+
+    // Steps until every ghost is on an end node at the same time. Once the ghosts and the
+    // commands are all back where they were at some step they never will be
+    fn naive_walk(
+        commands: &str,
+        nodes: &[(String, String, String)],
+        starts: &str,
+        ends: &str,
+    ) -> Option<usize> {
+        let network = nodes
+            .iter()
+            .map(|(name, left, right)| (name.as_str(), (left.as_str(), right.as_str())))
+            .collect::<HashMap<_, _>>();
+        let mut ghosts = network
+            .keys()
+            .copied()
+            .filter(|name| name.ends_with(starts))
+            .collect::<Vec<_>>();
+        let states = commands.len() * network.len().pow(ghosts.len() as u32);
+
+        for (steps, command) in commands.chars().cycle().enumerate().take(states + 1) {
+            if ghosts.iter().all(|ghost| ghost.ends_with(ends)) {
+                return Some(steps);
+            }
+
+            for ghost in &mut ghosts {
+                let (left, right) = network[ghost];
+                *ghost = if command == 'L' { left } else { right };
+            }
+        }
+
+        None
+    }
+
+    proptest! {
+        #[test]
+        fn matches_walking_every_step((commands, nodes) in ghost_map()) {
+            let map = to_map(&commands, &nodes);
+
+            prop_assert_eq!(naive_walk(&commands, &nodes, "AAA", "ZZZ"), part_one(&map));
+            prop_assert_eq!(naive_walk(&commands, &nodes, "A", "Z"), part_two(&map));
+        }
+
+        #[test]
+        fn matches_walking_any_map((commands, nodes) in any_map()) {
+            let map = to_map(&commands, &nodes);
+
+            prop_assert_eq!(naive_walk(&commands, &nodes, "A", "Z"), part_two(&map));
+        }
+    }
+
     #[test]
     fn example_part_one() {
         let map_str = "\
@@ -138,7 +488,7 @@ mod tests {
         AAA = (BBB, BBB)
         ZZZ = (ZZZ, ZZZ)";
 
-        assert_eq!(6, part_one(map_str));
+        assert_eq!(Some(6), part_one(map_str));
 
         let map_str = "\
         RL
@@ -151,7 +501,7 @@ mod tests {
         GGG = (GGG, GGG)
         ZZZ = (ZZZ, ZZZ)";
 
-        assert_eq!(2, part_one(map_str));
+        assert_eq!(Some(2), part_one(map_str));
     }
 
     #[test]
@@ -168,14 +518,96 @@ mod tests {
         22Z = (22B, 22B)
         XXX = (XXX, XXX)";
 
-        assert_eq!(6, part_two(map_str));
+        assert_eq!(Some(6), part_two(map_str));
+        assert_eq!(Some(6), part_two(&map_str.replace('\n', "\r\n")));
+    }
+
+    #[test]
+    fn walks_with_tails_and_offsets() {
+        // 11A walks two steps into a loop of three with an end node in it, 22A straight into a
+        // loop of four with end nodes at every odd step, then at every other one. The LCM of
+        // the cycles would say 12 both times
+        let map_str = "\
+        L
+
+        11A = (11B, 11B)
+        11B = (11C, 11C)
+        11C = (11Z, 11Z)
+        11Z = (11D, 11D)
+        11D = (11C, 11C)
+        22A = (22Z, 22Z)
+        22Z = (22B, 22B)
+        22B = (23Z, 23Z)
+        23Z = (22C, 22C)
+        22C = (22Z, 22Z)";
+
+        let map = GhostMap::parse_map(map_str).unwrap();
+        let walk = map.walk(0, |node| node.name.ends_with('Z'));
+        assert_eq!((2, 3, vec![3]), (walk.tail, walk.cycle, walk.hits));
+
+        assert_eq!(Some(3), part_two(map_str));
+        assert_eq!(Some(9), part_two(&map_str.replace("23Z", "22D")));
+    }
+
+    #[test]
+    fn never_meeting() {
+        let map_str = "\
+        L
+
+        11A = (11Z, 11Z)
+        11Z = (11B, 11B)
+        11B = (11Z, 11Z)
+        22A = (22B, 22B)
+        22B = (22Z, 22Z)
+        22Z = (22B, 22B)";
+
+        assert_eq!(None, part_two(map_str));
+        assert_eq!(None, part_two("L\n\n11Z = (11Z, 11Z)"));
+
+        let no_aaa = Day08::parse("LR\n\nBBB = (BBB, BBB)").unwrap();
+        assert_eq!("never", Day08::part_one(&no_aaa, &mut Explain::off()));
+        assert_eq!(None, part_one("LR\n\nBBB = (BBB, ZZZ)\nZZZ = (ZZZ, ZZZ)"));
+
+        let map = Day08::parse(map_str).unwrap();
+        assert_eq!("never", Day08::part_two(&map, &mut Explain::off()).unwrap());
+        assert!(Day08::parse("\n\nAAA = (AAA, AAA)").is_err());
+    }
+
+    #[test]
+    fn loops_full_of_end_nodes() {
+        // Each ghost goes round a loop where every node but its start is an end node, which
+        // would be millions of combinations of hits
+        let map_str = [53, 59, 61, 67, 71]
+            .iter()
+            .enumerate()
+            .flat_map(|(g, &length)| {
+                let name = move |k: usize| match k % length {
+                    0 => format!("{g}A"),
+                    k => format!("{g}{k:02}Z"),
+                };
+                (0..length).map(move |k| format!("{} = ({1}, {1})\n", name(k), name(k + 1)))
+            })
+            .fold(String::from("L\n\n"), |map, node| map + &node);
+
+        assert_eq!(Some(1), part_two(&map_str));
+
+        // Only the last quarter of each cycle hits, so they first meet well past every cycle
+        let walks = [53, 59, 61, 67, 71].map(|cycle| Walk {
+            tail: 0,
+            cycle,
+            hits: (cycle * 3 / 4..cycle).collect(),
+        });
+        let naive = (0..).find(|&steps| walks.iter().all(|walk| walk.hits_at(steps)));
+
+        assert!(naive > Some(71));
+        assert_eq!(naive, first_meeting(&walks));
     }
 
     #[test]
     fn real() {
         let map_str = include_str!("../res/day_08.txt");
 
-        assert_eq!(16043, part_one(map_str));
-        assert_eq!(15726453850399, part_two(map_str));
+        assert_eq!(Some(16043), part_one(map_str));
+        assert_eq!(Some(15726453850399), part_two(map_str));
     }
 }
@@ -1,61 +1,254 @@
-pub fn part_one(changes: &str) -> isize {
-    sum_predictions(changes, |oasis| oasis.predict_next())
+use num::{
+    bigint::BigInt,
+    rational::BigRational,
+    traits::{One, Signed, ToPrimitive, Zero},
+};
+use std::{
+    fmt,
+    io::{self, BufRead},
+};
+
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{numbers, ParseError, ParseResult, Required},
+    solution::{or_overflow, Solution},
+    stream::try_fold_lines,
+};
+
+pub fn part_one(changes: &str) -> Option<isize> {
+    sum_predictions(changes, OasisSequence::predict_next)
+}
+
+pub fn part_two(changes: &str) -> Option<isize> {
+    sum_predictions(changes, OasisSequence::predict_next_back)
+}
+
+pub fn part_one_buffered(reader: impl BufRead) -> io::Result<Option<isize>> {
+    try_fold_lines(reader, Some(0), |acc, line| {
+        let next = OasisSequence::parse(line)?.predict_next();
+        Ok(acc.zip(next).and_then(|(acc, next)| acc.checked_add(next)))
+    })
+}
+
+pub fn part_two_buffered(reader: impl BufRead) -> io::Result<Option<isize>> {
+    try_fold_lines(reader, Some(0), |acc, line| {
+        let before = OasisSequence::parse(line)?.predict_next_back();
+        Ok(acc
+            .zip(before)
+            .and_then(|(acc, before)| acc.checked_add(before)))
+    })
+}
+
+pub struct Day09;
+
+impl Solution for Day09 {
+    type Parsed<'a> = Vec<OasisSequence>;
+
+    fn parse(changes: &str) -> ParseResult<Self::Parsed<'_>> {
+        input::lines(changes).map(OasisSequence::parse).collect()
+    }
+
+    fn part_one(sequences: &Self::Parsed<'_>, explain: &mut Explain) -> String {
+        let nexts = sequences.iter().map(|oasis| {
+            let next = oasis.predict_next();
+            explain.step("sequence", || {
+                format!("{} -> {}", oasis.polynomial(), or_overflow(next))
+            });
+            next
+        });
+
+        or_overflow(checked_sum(nexts))
+    }
+
+    fn part_two(sequences: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        let befores = sequences.iter().map(OasisSequence::predict_next_back);
+
+        Some(or_overflow(checked_sum(befores)))
+    }
 }
 
-pub fn part_two(changes: &str) -> isize {
-    sum_predictions(changes, |oasis| oasis.predict_next_back())
+fn sum_predictions(
+    changes: &str,
+    predition: impl Fn(&OasisSequence) -> Option<isize>,
+) -> Option<isize> {
+    checked_sum(
+        input::lines(changes)
+            .map(|line| OasisSequence::parse(line).unwrap())
+            .map(|oasis| predition(&oasis)),
+    )
 }
 
-fn sum_predictions(changes: &str, predition: impl Fn(&OasisSequence) -> isize) -> isize {
-    changes
-        .lines()
-        .map(OasisSequence::parse)
-        .map(|oasis| predition(&oasis))
-        .sum()
+// None when a prediction or the sum doesn't fit
+fn checked_sum(predictions: impl IntoIterator<Item = Option<isize>>) -> Option<isize> {
+    predictions
+        .into_iter()
+        .try_fold(0_isize, |acc, prediction| acc.checked_add(prediction?))
 }
 
+// The values of a sequence are a polynomial in their index. It is kept in Newton's form, as the
+// first value of each row of differences down to the row of zeros: f(k) = sum of
+// newton[j] * C(k, j), where the binomials are integers for any k, even a negative one
 #[derive(Debug)]
-struct OasisSequence {
-    derived: Vec<Vec<isize>>,
+pub struct OasisSequence {
+    len: usize,
+    newton: Vec<isize>,
 }
 
 impl OasisSequence {
-    fn parse(line: &str) -> Self {
-        let sequence: Vec<isize> = line
-            .split_whitespace()
-            .map(|n| n.parse().unwrap())
-            .collect();
+    fn parse(line: &str) -> ParseResult<Self> {
+        let sequence: Vec<isize> = numbers(line)?;
+        let len = sequence.len();
 
-        let mut current_sequence = sequence.clone();
-        let mut derived = vec![sequence];
+        let mut current_sequence = sequence;
+        let mut newton = Vec::new();
 
+        // A sequence that runs out of values first would be extrapolated from a guess
         while current_sequence.iter().any(|n| *n != 0) {
-            let mut sequence_iter = current_sequence.into_iter();
-            let mut before = sequence_iter.next().unwrap_or(0);
-            let mut new_sequence = Vec::new();
+            newton.push(current_sequence[0]);
+
+            current_sequence = current_sequence
+                .windows(2)
+                .map(|pair| pair[1].checked_sub(pair[0]))
+                .collect::<Option<Vec<_>>>()
+                .required("differences that fit")?;
 
-            for item in sequence_iter {
-                new_sequence.push(item - before);
-                before = item;
+            if current_sequence.is_empty() {
+                return Err(ParseError::new(format!(
+                    "The differences of {line} never reach all zeros"
+                )));
             }
+        }
+
+        Ok(Self { len, newton })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The first value of each row of differences, the coefficients of C(k, j)
+    pub fn newton(&self) -> &[isize] {
+        &self.newton
+    }
+
+    pub fn degree(&self) -> Option<usize> {
+        self.newton.len().checked_sub(1)
+    }
+
+    pub fn polynomial(&self) -> Polynomial {
+        let mut coefficients = vec![BigRational::zero(); self.newton.len()];
+        // C(x, j) as a polynomial, starting from C(x, 0) = 1
+        let mut binomial = vec![BigRational::one()];
+
+        for (j, &difference) in self.newton.iter().enumerate() {
+            let difference = BigRational::from_integer(difference.into());
+
+            for (coefficient, term) in coefficients.iter_mut().zip(&binomial) {
+                *coefficient += &difference * term;
+            }
+
+            // C(x, j + 1) = C(x, j) * (x - j) / (j + 1)
+            let j = BigRational::from_integer(j.into());
+            let below = &j + BigRational::one();
+            let mut next = vec![BigRational::zero(); binomial.len() + 1];
+
+            for (power, term) in binomial.iter().enumerate() {
+                next[power + 1] += term / &below;
+                next[power] -= term * &j / &below;
+            }
+
+            binomial = next;
+        }
+
+        Polynomial { coefficients }
+    }
 
-            current_sequence = new_sequence.clone();
-            derived.push(new_sequence);
+    // The value at index `k`, the first value being at 0, or None when it doesn't fit
+    pub fn predict_at(&self, k: isize) -> Option<isize> {
+        let k = BigInt::from(k);
+        let mut binomial = BigInt::one();
+        let mut value = BigInt::zero();
+
+        for (j, &difference) in self.newton.iter().enumerate() {
+            value += &binomial * difference;
+            binomial = binomial * (&k - j) / (j + 1);
         }
 
-        Self { derived }
+        value.to_isize()
+    }
+
+    fn predict_next(&self) -> Option<isize> {
+        self.predict_at(self.len as isize)
     }
 
-    fn predict(&self, folding: impl Fn(isize, &Vec<isize>) -> isize) -> isize {
-        self.derived.iter().rev().skip(1).fold(0, folding)
+    fn predict_next_back(&self) -> Option<isize> {
+        self.predict_at(-1)
     }
+}
 
-    fn predict_next(&self) -> isize {
-        self.predict(|acc, elem| acc + elem.last().unwrap())
+// Exact coefficients, from the constant term up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    coefficients: Vec<BigRational>,
+}
+
+impl Polynomial {
+    pub fn coefficients(&self) -> &[BigRational] {
+        &self.coefficients
     }
 
-    fn predict_next_back(&self) -> isize {
-        self.predict(|acc, elem| elem.first().unwrap() - acc)
+    pub fn evaluate(&self, x: &BigRational) -> BigRational {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(BigRational::zero(), |acc, coefficient| {
+                acc * x + coefficient
+            })
+    }
+}
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, coefficient)| !coefficient.is_zero())
+            .peekable();
+
+        if terms.peek().is_none() {
+            return write!(f, "0");
+        }
+
+        for (i, (power, coefficient)) in terms.enumerate() {
+            let sign = if coefficient.is_negative() { "-" } else { "+" };
+            match (i, sign) {
+                (0, "+") => {}
+                (0, _) => write!(f, "-")?,
+                _ => write!(f, " {sign} ")?,
+            }
+
+            let size = coefficient.abs();
+            if power == 0 {
+                write!(f, "{size}")?;
+            } else if !size.is_one() {
+                write!(f, "{size}*")?;
+            }
+
+            match power {
+                0 => {}
+                1 => write!(f, "x")?,
+                _ => write!(f, "x^{power}")?,
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -70,27 +263,88 @@ mod tests {
         1 3 6 10 15 21
         10 13 16 21 30 45";
 
-        let sequence = OasisSequence::parse("0 3 6 9 12 15");
-        assert_eq!(18, sequence.predict_next());
-        assert_eq!(-3, sequence.predict_next_back());
+        let sequence = OasisSequence::parse("0 3 6 9 12 15").unwrap();
+        assert_eq!(Some(18), sequence.predict_next());
+        assert_eq!(Some(-3), sequence.predict_next_back());
+
+        let sequence = OasisSequence::parse("1 3 6 10 15 21").unwrap();
+        assert_eq!(Some(28), sequence.predict_next());
+        assert_eq!(Some(0), sequence.predict_next_back());
+
+        let sequence = OasisSequence::parse("10 13 16 21 30 45").unwrap();
+        assert_eq!(Some(68), sequence.predict_next());
+        assert_eq!(Some(5), sequence.predict_next_back());
+
+        let sequence = OasisSequence::parse("1 3 6 10 15 21").unwrap();
+        assert_eq!("1/2*x^2 + 3/2*x + 1", sequence.polynomial().to_string());
 
-        let sequence = OasisSequence::parse("1 3 6 10 15 21");
-        assert_eq!(28, sequence.predict_next());
-        assert_eq!(0, sequence.predict_next_back());
+        assert_eq!(Some(114), part_one(input));
+        assert_eq!(Some(2), part_two(input));
+    }
+
+    #[test]
+    fn closed_form() {
+        let sequence = OasisSequence::parse("10 13 16 21 30 45").unwrap();
+        assert_eq!(&[10, 3, 0, 2], sequence.newton());
+        assert_eq!(Some(3), sequence.degree());
+        assert_eq!(
+            "1/3*x^3 - x^2 + 11/3*x + 10",
+            sequence.polynomial().to_string()
+        );
+
+        for k in -20..20 {
+            let x = BigRational::from_integer(k.into());
+            let value = sequence.polynomial().evaluate(&x).to_integer();
+            assert_eq!(value.to_isize(), sequence.predict_at(k));
+        }
+
+        assert_eq!(Some(45), sequence.predict_at(5));
+        assert_eq!(Some(-4), sequence.predict_at(-2));
+        assert_eq!(None, sequence.predict_at(isize::MAX));
+
+        let flat = OasisSequence::parse("0 0 0").unwrap();
+        assert_eq!(None, flat.degree());
+        assert_eq!("0", flat.polynomial().to_string());
+        assert_eq!(Some(0), flat.predict_at(-7));
+
+        let falling = OasisSequence::parse("4 1 -2").unwrap();
+        assert_eq!("-3*x + 4", falling.polynomial().to_string());
+    }
+
+    #[test]
+    fn never_settling() {
+        assert!(OasisSequence::parse("1 2 4 8 16").is_err());
+        assert!(OasisSequence::parse("7").is_err());
+        assert!(Day09::parse("0 3 6\n1 2 4").is_err());
+        assert!(part_one_buffered("1 -1 1 -1".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        let rising = "0 4000000000000000000 8000000000000000000";
+        let sequences = Day09::parse(rising).unwrap();
 
-        let sequence = OasisSequence::parse("10 13 16 21 30 45");
-        assert_eq!(68, sequence.predict_next());
-        assert_eq!(5, sequence.predict_next_back());
+        assert_eq!(None, part_one(rising));
+        assert_eq!(Some(-4000000000000000000), part_two(rising));
+        assert_eq!("overflow", Day09::part_one(&sequences, &mut Explain::off()));
+        assert_eq!(None, part_one_buffered(rising.as_bytes()).unwrap());
 
-        assert_eq!(114, part_one(input));
-        assert_eq!(2, part_two(input));
+        // Each value before fits, but not their sum
+        let flat = "-5000000000000000000 -5000000000000000000\n".repeat(2);
+        assert_eq!(None, part_two(&flat));
+        assert_eq!(None, part_two_buffered(flat.as_bytes()).unwrap());
     }
 
     #[test]
     fn real() {
         let input = include_str!("../res/day_09.txt");
 
-        assert_eq!(2043183816, part_one(input));
-        assert_eq!(1118, part_two(input));
+        assert_eq!(Some(2043183816), part_one(input));
+        assert_eq!(Some(1118), part_two(input));
+        assert_eq!(
+            Some(2043183816),
+            part_one_buffered(input.as_bytes()).unwrap()
+        );
+        assert_eq!(Some(1118), part_two_buffered(input.as_bytes()).unwrap());
     }
 }
@@ -1,9 +1,41 @@
 use std::{cell::RefCell, rc::Rc, vec};
 
-use crate::common::Point;
+use crate::common::{
+    explain::Explain,
+    parse::{self, ParseError, ParseResult, Required},
+    solution::Solution,
+    Point,
+};
 
 pub fn part_one(maze: &str) -> usize {
-    let pipe_world = parse_pipe_world(maze);
+    farthest_distance(&parse_pipe_kinds(maze).unwrap())
+}
+
+pub fn part_two(maze: &str) -> usize {
+    enclosed_tiles(&parse_pipe_kinds(maze).unwrap())
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    // Only the pipe kinds, as walking the loop marks the pipes
+    type Parsed<'a> = Vec<Vec<PipeType>>;
+
+    fn parse(maze: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse_pipe_kinds(maze)
+    }
+
+    fn part_one(kinds: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        farthest_distance(kinds).to_string()
+    }
+
+    fn part_two(kinds: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(enclosed_tiles(kinds).to_string())
+    }
+}
+
+fn farthest_distance(kinds: &[Vec<PipeType>]) -> usize {
+    let pipe_world = build_pipe_world(kinds);
     let pipe_loop = find_connections_and_distances(&pipe_world);
 
     let max = pipe_loop
@@ -12,11 +44,11 @@ pub fn part_one(maze: &str) -> usize {
         .max()
         .unwrap_or(0);
 
-    (max + 1) / 2
+    max.div_ceil(2)
 }
 
-pub fn part_two(maze: &str) -> usize {
-    let pipe_world = parse_pipe_world(maze);
+fn enclosed_tiles(kinds: &[Vec<PipeType>]) -> usize {
+    let pipe_world = build_pipe_world(kinds);
     let vertices = find_connections_and_distances(&pipe_world);
     let lines = pipe_world.len();
     let columns = pipe_world[0].len();
@@ -35,8 +67,8 @@ pub fn part_two(maze: &str) -> usize {
     (area - vertices.len() as f64 / 2.0 + 1.0) as usize
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum PipeType {
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PipeType {
     Vertical,
     Horizontal,
     NorthEast,
@@ -48,8 +80,8 @@ enum PipeType {
 }
 
 impl PipeType {
-    fn parse(char: char) -> Self {
-        match char {
+    fn parse(char: char) -> ParseResult<Self> {
+        Ok(match char {
             '|' => Self::Vertical,
             '-' => Self::Horizontal,
             'L' => Self::NorthEast,
@@ -58,8 +90,8 @@ impl PipeType {
             'F' => Self::SouthEast,
             '.' => Self::Ground,
             'S' => Self::StartingPosition,
-            _ => panic!("Unknow pipe {}", char),
-        }
+            _ => return Err(ParseError::new(format!("Unknown pipe {char}"))),
+        })
     }
 
     // North, East, South, West
@@ -184,18 +216,102 @@ impl PartialEq for Pipe {
 
 type PipeWorld = Vec<Vec<Rc<RefCell<Pipe>>>>;
 
-fn parse_pipe_world(input: &str) -> PipeWorld {
-    input
-        .lines()
+fn parse_pipe_kinds(maze: &str) -> ParseResult<Vec<Vec<PipeType>>> {
+    let kinds = parse::grid(maze, PipeType::parse)?;
+
+    let starts = kinds
+        .iter()
+        .flatten()
+        .filter(|kind| **kind == PipeType::StartingPosition)
+        .count();
+    if starts != 1 {
+        return Err(ParseError::new(format!(
+            "Expected one starting position, found {starts}"
+        )));
+    }
+
+    check_loop(&kinds)?;
+    Ok(kinds)
+}
+
+// Both parts walk the loop trusting every pipe on it to lead on, so follow it once here the way
+// they do: out of the start through its first connection, until it comes back
+fn check_loop(kinds: &[Vec<PipeType>]) -> ParseResult<()> {
+    // North, East, South, West, as in `PipeType::connections`
+    let step = |(line, column): (usize, usize), direction: usize| {
+        let next = match direction {
+            0 => (line.checked_sub(1)?, column),
+            1 => (line, column + 1),
+            2 => (line + 1, column),
+            _ => (line, column.checked_sub(1)?),
+        };
+        kinds.get(next.0)?.get(next.1)?;
+        Some(next)
+    };
+    let opens = |(line, column): (usize, usize)| {
+        let (north, east, south, west) = kinds[line][column].connections();
+        [north, east, south, west]
+    };
+    // The tiles this one connects to, that connect back to it
+    let connected = |tile: (usize, usize)| {
+        (0..4)
+            .filter(|&direction| opens(tile)[direction])
+            .filter_map(|direction| {
+                step(tile, direction).filter(|&next| opens(next)[(direction + 2) % 4])
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let start = kinds
+        .iter()
+        .enumerate()
+        .find_map(|(line, row)| {
+            row.iter()
+                .position(|kind| *kind == PipeType::StartingPosition)
+                .map(|column| (line, column))
+        })
+        .required("the starting position")?;
+
+    let from_start = connected(start);
+    if from_start.len() < 2 {
+        return Err(ParseError::new("The start doesn't connect to two pipes"));
+    }
+
+    let (mut previous, mut current) = (start, from_start[0]);
+
+    while current != start {
+        let next = connected(current);
+        let next = next
+            .iter()
+            .find(|&&next| next != previous)
+            .filter(|_| next.len() == 2)
+            .ok_or_else(|| {
+                ParseError::new(format!(
+                    "The loop breaks at line {}, column {}",
+                    current.0 + 1,
+                    current.1 + 1
+                ))
+            })?;
+
+        (previous, current) = (current, *next);
+    }
+
+    Ok(())
+}
+
+fn build_pipe_world(kinds: &[Vec<PipeType>]) -> PipeWorld {
+    kinds
+        .iter()
         .enumerate()
         .map(|(line_number, line)| {
-            line.trim()
-                .chars()
+            line.iter()
                 .enumerate()
-                .map(|(column_number, column)| {
-                    let kind = PipeType::parse(column);
-
-                    Rc::new(RefCell::new(Pipe::new(kind, line_number, column_number)))
+                .map(|(column_number, kind)| {
+                    Rc::new(RefCell::new(Pipe::new(
+                        kind.clone(),
+                        line_number,
+                        column_number,
+                    )))
                 })
                 .collect::<Vec<_>>()
         })
@@ -249,7 +365,62 @@ fn visit_connections(
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
+    use proptest::prelude::*;
+
     use super::*;
+    use crate::generate::pipe_maze;
+    use crate::strategies::{flood_outside, rectilinear_loop};
+
+    type Tile = (usize, usize);
+
+    fn maze() -> impl Strategy<Value = (String, Vec<Tile>)> {
+        let junk = prop::sample::select(vec!['|', '-', 'L', 'J', '7', 'F', '.']);
+
+        (
+            rectilinear_loop(6, 2),
+            any::<prop::sample::Index>(),
+            prop::collection::vec(junk, 64),
+        )
+            .prop_map(|(points, start, junk)| {
+                let mut junk = junk.into_iter().cycle();
+                pipe_maze(&points, start.index(points.len()), || junk.next().unwrap())
+            })
+    }
+
+    // Every tile blown up to 3x3 with the loop drawn in it, so flooding from the outside can
+    // squeeze between pipes. Tiles whose center isn't reached are enclosed
+    fn naive_enclosed(maze: &str, tiles: &[Tile]) -> usize {
+        let lines = maze.lines().collect::<Vec<_>>();
+        let (width, height) = (lines[0].len(), lines.len());
+        let mut walls = HashSet::new();
+
+        let n = tiles.len();
+        for (i, &(x, y)) in tiles.iter().enumerate() {
+            walls.insert((3 * x + 1, 3 * y + 1));
+
+            for (other_x, other_y) in [tiles[(i + n - 1) % n], tiles[(i + 1) % n]] {
+                walls.insert((3 * x + 1 + other_x - x, 3 * y + 1 + other_y - y));
+            }
+        }
+
+        let outside = flood_outside(3 * width, 3 * height, |x, y| walls.contains(&(x, y)));
+        let in_loop = tiles.iter().collect::<HashSet<_>>();
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|tile| !in_loop.contains(tile) && !outside[3 * tile.1 + 1][3 * tile.0 + 1])
+            .count()
+    }
+
+    proptest! {
+        #[test]
+        fn matches_flooding((maze, tiles) in maze()) {
+            prop_assert_eq!(tiles.len() / 2, part_one(&maze));
+            prop_assert_eq!(naive_enclosed(&maze, &tiles), part_two(&maze));
+        }
+    }
 
     #[test]
     fn example() {
@@ -304,6 +475,22 @@ mod test {
         assert_eq!(8, part_two(input));
     }
 
+    #[test]
+    fn broken_loops() {
+        // The bottom right corner is missing
+        let open = "\
+        .....
+        .S-7.
+        .|.|.
+        .L-..
+        .....";
+        assert!(Day10::parse(open).is_err());
+        assert!(Day10::parse(&open.replace("L-..", "L-J.")).is_ok());
+
+        assert!(Day10::parse("S-7\n..|").is_err());
+        assert!(Day10::parse("S..\n...").is_err());
+    }
+
     #[test]
     fn real() {
         let input = include_str!("../res/day_10.txt");
@@ -1,32 +1,94 @@
-use std::collections::HashSet;
-
-use crate::common::Point;
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{ParseError, ParseResult},
+    solution::Solution,
+    Point,
+};
 
 pub fn calculate_distances(universe_str: &str, expansion: usize) -> usize {
-    let universe = Universe::parse_galaxy(universe_str, expansion);
+    Universe::parse_galaxy(universe_str)
+        .unwrap()
+        .total_distance(Expansion::uniform(expansion))
+}
 
-    let pairs = Point::point_pairs(&universe.galaxies);
+pub fn part_one(universe_str: &str) -> usize {
+    calculate_distances(universe_str, 2)
+}
 
-    pairs
-        .into_iter()
-        .map(|(p1, p2)| p1.x.abs_diff(p2.x) + p1.y.abs_diff(p2.y))
-        .sum()
+pub fn part_two(universe_str: &str) -> usize {
+    calculate_distances(universe_str, 1_000_000)
+}
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    type Parsed<'a> = Universe;
+
+    fn parse(universe_str: &str) -> ParseResult<Self::Parsed<'_>> {
+        Universe::parse_galaxy(universe_str)
+    }
+
+    fn part_one(universe: &Self::Parsed<'_>, explain: &mut Explain) -> String {
+        explain.step("universe", || {
+            format!(
+                "{} galaxies, {} empty lines, {} empty columns",
+                universe.galaxies.len(),
+                universe.empty_lines.len(),
+                universe.empty_columns.len()
+            )
+        });
+
+        universe.total_distance(Expansion::uniform(2)).to_string()
+    }
+
+    fn part_two(universe: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(
+            universe
+                .total_distance(Expansion::uniform(1_000_000))
+                .to_string(),
+        )
+    }
+}
+
+// How many lines or columns each empty one becomes, along each axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expansion {
+    pub columns: usize,
+    pub lines: usize,
 }
 
-#[derive(Debug)]
-struct Universe {
+impl Expansion {
+    pub fn uniform(amount: usize) -> Self {
+        Self {
+            columns: amount,
+            lines: amount,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Universe {
     galaxies: Vec<Point>,
-    expansion_lines: Vec<isize>,
-    expansion_columns: Vec<isize>,
+    empty_lines: Vec<isize>,
+    empty_columns: Vec<isize>,
 }
 
 impl Universe {
-    fn parse_galaxy(universe_str: &str, expansion: usize) -> Self {
-        let galaxies = universe_str
-            .lines()
+    fn parse_galaxy(universe_str: &str) -> ParseResult<Self> {
+        if let Some(c) = universe_str
+            .chars()
+            .find(|c| !matches!(c, '.' | '#') && !c.is_whitespace())
+        {
+            return Err(ParseError::new(format!(
+                "Unexpected char {c:?} in the image"
+            )));
+        }
+
+        let galaxies = input::lines(universe_str)
             .enumerate()
             .flat_map(|(y, line)| {
-                line.trim().chars().enumerate().filter_map(move |(x, c)| {
+                line.chars().enumerate().filter_map(move |(x, c)| {
                     if c == '#' {
                         Some(Point::new_usize(x, y))
                     } else {
@@ -36,53 +98,162 @@ impl Universe {
             })
             .collect::<Vec<_>>();
 
-        let expansion_lines = Self::find_empty(&galaxies, |p| p.y);
-        let expansion_columns = Self::find_empty(&galaxies, |p| p.x);
+        let empty_lines = Self::find_empty(&galaxies, |p| p.y);
+        let empty_columns = Self::find_empty(&galaxies, |p| p.x);
 
-        let mut universe = Self {
+        Ok(Self {
             galaxies,
-            expansion_lines,
-            expansion_columns,
-        };
-
-        universe.expand(expansion);
-        universe
+            empty_lines,
+            empty_columns,
+        })
     }
 
+    // Sorted, so expanding a coordinate is a binary search
     fn find_empty(galaxies: &[Point], mapper: impl Fn(&Point) -> isize) -> Vec<isize> {
-        let coordinates = galaxies.iter().map(mapper).collect::<HashSet<_>>();
+        let mut coordinates = galaxies.iter().map(mapper).collect::<Vec<_>>();
+        coordinates.sort_unstable();
+        coordinates.dedup();
 
-        (0..coordinates.iter().max().cloned().unwrap_or(0))
-            .filter(|x| !coordinates.contains(x))
+        std::iter::once(-1)
+            .chain(coordinates.iter().copied())
+            .zip(&coordinates)
+            .flat_map(|(low, &high)| low + 1..high)
             .collect()
     }
 
-    fn expand(&mut self, amount: usize) {
-        assert!(amount > 0, "Expansion amount must be positive");
+    pub fn len(&self) -> usize {
+        self.galaxies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.galaxies.is_empty()
+    }
+
+    // Where the galaxies are once the empty lines and columns have grown
+    pub fn galaxies(&self, expansion: Expansion) -> Vec<Point> {
+        self.galaxies
+            .iter()
+            .map(|galaxy| self.expand(galaxy, expansion))
+            .collect()
+    }
 
-        for point in self.galaxies.iter_mut() {
-            let times_x = self
-                .expansion_columns
-                .iter()
-                .filter(|x| **x < point.x)
-                .count();
+    fn expand(&self, galaxy: &Point, expansion: Expansion) -> Point {
+        assert!(
+            expansion.columns > 0 && expansion.lines > 0,
+            "Expansion amount must be positive"
+        );
 
-            let times_y = self
-                .expansion_lines
-                .iter()
-                .filter(|y| **y < point.y)
-                .count();
+        let grow = |empty: &[isize], coordinate: isize, amount: usize| {
+            let times = empty.partition_point(|&e| e < coordinate);
+            coordinate + (times * (amount - 1)) as isize
+        };
 
-            point.x += (times_x * (amount - 1)) as isize;
-            point.y += (times_y * (amount - 1)) as isize;
-        }
+        Point::new(
+            grow(&self.empty_columns, galaxy.x, expansion.columns),
+            grow(&self.empty_lines, galaxy.y, expansion.lines),
+        )
+    }
+
+    // The sum over every pair, one axis at a time: once sorted, the i-th coordinate is the far
+    // end of i pairs, so it adds i times itself minus everything before it
+    pub fn total_distance(&self, expansion: Expansion) -> usize {
+        let galaxies = self.galaxies(expansion);
+
+        [
+            galaxies.iter().map(|p| p.x).collect::<Vec<_>>(),
+            galaxies.iter().map(|p| p.y).collect(),
+        ]
+        .into_iter()
+        .map(|mut axis| {
+            axis.sort_unstable();
+
+            let mut before = 0;
+            axis.iter()
+                .enumerate()
+                .map(|(i, &coordinate)| {
+                    let distance = coordinate * i as isize - before;
+                    before += coordinate;
+                    distance as usize
+                })
+                .sum::<usize>()
+        })
+        .sum()
+    }
+
+    // Between the galaxies at these positions in reading order
+    pub fn distance(&self, first: usize, second: usize, expansion: Expansion) -> Option<usize> {
+        let first = self.expand(self.galaxies.get(first)?, expansion);
+        let second = self.expand(self.galaxies.get(second)?, expansion);
+
+        Some(first.x.abs_diff(second.x) + first.y.abs_diff(second.y))
+    }
+
+    // The two galaxies farthest apart and their distance. Along x + y or x - y the distance
+    // is the difference between the largest and the smallest value
+    pub fn farthest_pair(&self, expansion: Expansion) -> Option<(usize, usize, usize)> {
+        let galaxies = self.galaxies(expansion);
+
+        [|p: &Point| p.x + p.y, |p: &Point| p.x - p.y]
+            .into_iter()
+            .filter_map(|rotated| {
+                let (low, _) = galaxies
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, p)| rotated(p))?;
+                let (high, _) = galaxies
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, p)| rotated(p))?;
+
+                Some((
+                    low.min(high),
+                    low.max(high),
+                    rotated(&galaxies[high]).abs_diff(rotated(&galaxies[low])),
+                ))
+            })
+            .max_by_key(|&(_, _, distance)| distance)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
+    fn naive_distances(universe: &Universe, expansion: Expansion) -> usize {
+        Point::point_pairs(&universe.galaxies(expansion))
+            .into_iter()
+            .map(|(p1, p2)| p1.x.abs_diff(p2.x) + p1.y.abs_diff(p2.y))
+            .sum()
+    }
+
+    proptest! {
+        #[test]
+        fn matches_every_pair(
+            image in prop::collection::vec("[.#]{1,12}", 1..12),
+            columns in 1..20_usize,
+            lines in 1..20_usize,
+        ) {
+            let universe = Universe::parse_galaxy(&image.join("\n")).unwrap();
+            let expansion = Expansion { columns, lines };
+            let galaxies = universe.galaxies(expansion);
+
+            prop_assert_eq!(naive_distances(&universe, expansion), universe.total_distance(expansion));
+
+            let farthest = Point::point_pairs(&galaxies)
+                .into_iter()
+                .map(|(p1, p2)| p1.x.abs_diff(p2.x) + p1.y.abs_diff(p2.y))
+                .max();
+            let found = universe.farthest_pair(expansion);
+            prop_assert_eq!(farthest, found.filter(|_| galaxies.len() > 1).map(|(_, _, d)| d));
+
+            if let Some((first, second, distance)) = found {
+                prop_assert_eq!(Some(distance), universe.distance(first, second, expansion));
+            }
+        }
+    }
+
     #[test]
     fn example() {
         let input = "\
@@ -100,6 +271,38 @@ mod tests {
         assert_eq!(374, calculate_distances(input, 2));
         assert_eq!(1030, calculate_distances(input, 10));
         assert_eq!(8410, calculate_distances(input, 100));
+
+        let universe = Universe::parse_galaxy(input).unwrap();
+        let twice = Expansion::uniform(2);
+        assert_eq!(Some(9), universe.distance(4, 8, twice));
+        assert_eq!(Some(15), universe.distance(0, 6, twice));
+        assert_eq!(Some(17), universe.distance(2, 5, twice));
+        assert_eq!(Some(5), universe.distance(7, 8, twice));
+        assert_eq!(None, universe.distance(7, 9, twice));
+    }
+
+    #[test]
+    fn axes_apart() {
+        let input = "\
+        #..
+        ...
+        ..#";
+
+        let universe = Universe::parse_galaxy(input).unwrap();
+        let wide = Expansion {
+            columns: 10,
+            lines: 1,
+        };
+        assert_eq!(
+            vec![Point::new(0, 0), Point::new(11, 2)],
+            universe.galaxies(wide)
+        );
+        assert_eq!(13, universe.total_distance(wide));
+        assert_eq!(Some((0, 1, 13)), universe.farthest_pair(wide));
+
+        let universe = Universe::parse_galaxy("..#\n...\n...").unwrap();
+        assert_eq!(0, universe.total_distance(wide));
+        assert_eq!(Some(Point::new(20, 0)), universe.galaxies(wide).pop());
     }
 
     #[test]
@@ -1,108 +1,256 @@
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    hash::{Hash, Hasher},
-    sync::Mutex,
+    collections::HashMap,
+    io::{self, BufRead},
 };
 
-pub fn part_one(diagrams: &str) -> usize {
-    let records = Record::parse(diagrams);
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseError, ParseResult, Required},
+    solution::{or_overflow, Solution},
+    stream::try_fold_lines,
+};
+
+pub fn part_one(diagrams: &str) -> Option<usize> {
+    let records = Record::parse(diagrams).unwrap();
 
-    records
-        .into_iter()
-        .map(|record| record.as_record_ref().count_possible())
-        .sum()
+    total(records.iter().map(Record::count_possible))
 }
 
-pub fn part_two(diagrams: &str) -> usize {
-    let records = Record::parse(diagrams);
+pub fn part_two(diagrams: &str) -> Option<usize> {
+    let records = Record::parse(diagrams).unwrap();
 
-    records
-        .into_iter()
-        .map(|record| {
-            let mut five_springs = Vec::new();
+    total(
+        records
+            .iter()
+            .map(|record| record.unfold().count_possible()),
+    )
+}
 
-            for i in 0..5 {
-                five_springs.extend(record.springs.clone());
-                if i != 4 {
-                    five_springs.push(SpringType::Unknown);
-                }
-            }
+pub struct Day12;
 
-            Record {
-                springs: five_springs,
-                groups: record
-                    .groups
-                    .iter()
-                    .cycle()
-                    .take(record.groups.len() * 5)
-                    .cloned()
-                    .collect(),
-            }
-        })
-        .map(|record| record.as_record_ref().count_possible())
-        .sum()
+impl Solution for Day12 {
+    type Parsed<'a> = Vec<Record>;
+
+    fn parse(diagrams: &str) -> ParseResult<Self::Parsed<'_>> {
+        Record::parse(diagrams)
+    }
+
+    fn part_one(records: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        or_overflow(total(records.iter().map(Record::count_possible)))
+    }
+
+    fn part_two(records: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        let counts = records
+            .iter()
+            .map(|record| record.unfold().count_possible());
+
+        Some(or_overflow(total(counts)))
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
-enum SpringType {
+pub fn part_one_buffered(reader: impl BufRead) -> io::Result<Option<usize>> {
+    try_fold_lines(reader, Some(0), |acc, line| {
+        Ok(add(acc, Record::parse_line(line)?.count_possible()))
+    })
+}
+
+pub fn part_two_buffered(reader: impl BufRead) -> io::Result<Option<usize>> {
+    try_fold_lines(reader, Some(0), |acc, line| {
+        Ok(add(
+            acc,
+            Record::parse_line(line)?.unfold().count_possible(),
+        ))
+    })
+}
+
+// Counts are None once they don't fit
+fn add(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    a?.checked_add(b?)
+}
+
+fn total(counts: impl IntoIterator<Item = Option<usize>>) -> Option<usize> {
+    counts.into_iter().fold(Some(0), add)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SpringType {
     Functional,
     Broken,
     Unknown,
 }
 
 impl SpringType {
-    fn parse(spring_char: char) -> Self {
+    pub fn parse(spring_char: char) -> ParseResult<Self> {
         match spring_char {
-            '.' => Self::Functional,
-            '#' => Self::Broken,
-            '?' => Self::Unknown,
-            _ => panic!("Invalid spring type {}", spring_char),
+            '.' => Ok(Self::Functional),
+            '#' => Ok(Self::Broken),
+            '?' => Ok(Self::Unknown),
+            _ => Err(ParseError::new(format!(
+                "Invalid spring type {spring_char}"
+            ))),
         }
     }
 }
 
 #[derive(Debug)]
-struct Record {
+pub struct Record {
     springs: Vec<SpringType>,
     groups: Vec<usize>,
 }
 
-#[derive(Debug, Hash)]
+#[derive(Debug)]
 struct RecordRef<'a> {
     springs: &'a [SpringType],
     groups: &'a [usize],
 }
 
-lazy_static! {
-    static ref CACHE: Mutex<HashMap<u64, usize>> = Mutex::new(HashMap::new());
-}
+// Every RecordRef of a record is a suffix of it, so the remaining lengths identify it
+type Cache = HashMap<(usize, usize), Option<usize>>;
 
 impl Record {
-    fn parse(diagrams: &str) -> Vec<Self> {
-        diagrams
-            .lines()
-            .flat_map(|line| {
-                let line = line.trim();
-
-                let mut parts = line.split_whitespace();
-
-                let springs = parts
-                    .next()?
-                    .chars()
-                    .map(SpringType::parse)
-                    .collect::<Vec<_>>();
-                let groups = parts
-                    .next()?
-                    .split(',')
-                    .flat_map(|number| number.parse())
-                    .collect::<Vec<usize>>();
+    pub fn new(springs: Vec<SpringType>, groups: Vec<usize>) -> Self {
+        Self { springs, groups }
+    }
 
-                Some(Self { springs, groups })
-            })
-            .collect()
+    fn parse(diagrams: &str) -> ParseResult<Vec<Self>> {
+        input::lines(diagrams).map(Self::parse_line).collect()
     }
 
-    fn as_record_ref(&self) -> RecordRef {
+    fn parse_line(line: &str) -> ParseResult<Self> {
+        let mut parts = line.split_whitespace();
+
+        let springs = parts
+            .next()
+            .required("the springs")?
+            .chars()
+            .map(SpringType::parse)
+            .collect::<ParseResult<Vec<_>>>()?;
+        let groups = parts
+            .next()
+            .required("the groups")?
+            .split(',')
+            .map(number)
+            .collect::<ParseResult<Vec<usize>>>()?;
+
+        Ok(Self { springs, groups })
+    }
+
+    fn unfold(&self) -> Self {
+        let mut five_springs = Vec::new();
+
+        for i in 0..5 {
+            five_springs.extend(self.springs.clone());
+            if i != 4 {
+                five_springs.push(SpringType::Unknown);
+            }
+        }
+
+        Self {
+            springs: five_springs,
+            groups: self
+                .groups
+                .iter()
+                .cycle()
+                .take(self.groups.len() * 5)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn count_possible(&self) -> Option<usize> {
+        self.as_record_ref().count_possible(&mut Cache::new())
+    }
+
+    // What every arrangement agrees on: Broken or Functional where they all do, Unknown where
+    // they differ, None when there is no arrangement. A spring can be broken when some group
+    // fits over it with arrangements of the groups before it in the springs before it, and of
+    // the groups after it in the springs after it. It can be functional when the groups split
+    // into some before it and the rest after it the same way. Only whether there are any
+    // arrangements matters, so counts that don't fit still say there are
+    pub fn forced(&self) -> Option<Vec<SpringType>> {
+        let (len, groups) = (self.springs.len(), self.groups.len());
+
+        // The reversed record's suffixes are this record's prefixes
+        let reversed = Self {
+            springs: self.springs.iter().rev().copied().collect(),
+            groups: self.groups.iter().rev().copied().collect(),
+        };
+        let (mut prefix_cache, mut suffix_cache) = (Cache::new(), Cache::new());
+        let mut prefix = |springs: usize, groups_before: usize| {
+            RecordRef {
+                springs: &reversed.springs[len - springs..],
+                groups: &reversed.groups[groups - groups_before..],
+            }
+            .count_possible(&mut prefix_cache)
+                != Some(0)
+        };
+        let mut suffix = |from: usize, group: usize| {
+            RecordRef {
+                springs: &self.springs[from.min(len)..],
+                groups: &self.groups[group..],
+            }
+            .count_possible(&mut suffix_cache)
+                != Some(0)
+        };
+
+        if !suffix(0, 0) {
+            return None;
+        }
+
+        // Groups that can start or end a broken run at each spring, summed up afterwards
+        let mut broken_runs = vec![0_isize; len + 1];
+
+        for (group, &size) in self.groups.iter().enumerate() {
+            for start in 0..(len + 1).saturating_sub(size) {
+                let end = start + size;
+
+                if self.springs[start..end].contains(&SpringType::Functional)
+                    || (start > 0 && self.springs[start - 1] == SpringType::Broken)
+                    || self.springs.get(end) == Some(&SpringType::Broken)
+                {
+                    continue;
+                }
+
+                let before = if start == 0 {
+                    group == 0
+                } else {
+                    prefix(start - 1, group)
+                };
+                let after = if end == len {
+                    group + 1 == groups
+                } else {
+                    suffix(end + 1, group + 1)
+                };
+
+                if before && after {
+                    broken_runs[start] += 1;
+                    broken_runs[end] -= 1;
+                }
+            }
+        }
+
+        let mut broken = 0;
+
+        Some(
+            (0..len)
+                .map(|spring| {
+                    broken += broken_runs[spring];
+                    let can_work = self.springs[spring] != SpringType::Broken
+                        && (0..=groups)
+                            .any(|split| prefix(spring, split) && suffix(spring + 1, split));
+
+                    match (broken > 0, can_work) {
+                        (true, false) => SpringType::Broken,
+                        (false, _) => SpringType::Functional,
+                        (true, true) => SpringType::Unknown,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn as_record_ref(&self) -> RecordRef<'_> {
         RecordRef {
             springs: &self.springs,
             groups: &self.groups,
@@ -111,28 +259,28 @@ impl Record {
 }
 
 impl<'a> RecordRef<'a> {
-    fn count_possible(&self) -> usize {
+    fn count_possible(&self, cache: &mut Cache) -> Option<usize> {
         // Handle base cases
         match (self.springs.is_empty(), self.groups.is_empty()) {
-            (true, true) => return 1,  // We're done
-            (true, false) => return 0, // No possible spring to match the group
+            (true, true) => return Some(1),  // We're done
+            (true, false) => return Some(0), // No possible spring to match the group
             // No more groups to match a broken spring. Else we have 1 possibility: all functional
             (false, true) => {
                 return if self.springs.contains(&SpringType::Broken) {
-                    0
+                    Some(0)
                 } else {
-                    1
+                    Some(1)
                 }
             }
             _ => (),
         }
 
         // Check cache
-        if let Some(&total) = CACHE.lock().unwrap().get(&self.default_hash()) {
+        if let Some(&total) = cache.get(&self.key()) {
             return total;
         }
 
-        let mut total = 0;
+        let mut total = Some(0);
         let spring = &self.springs[0];
 
         // Functional or Unknown spring case. Just check the rest
@@ -141,7 +289,7 @@ impl<'a> RecordRef<'a> {
                 springs: &self.springs[1..],
                 groups: self.groups,
             };
-            total += partial.count_possible();
+            total = add(total, partial.count_possible(cache));
         }
 
         // Broken or Unknown spring case. Check if the group could match and then check the rest removing the group
@@ -155,31 +303,159 @@ impl<'a> RecordRef<'a> {
             && self
                 .springs
                 .get(self.groups[0])
-                .map_or(true, |s| *s != SpringType::Broken)
+                .is_none_or(|s| *s != SpringType::Broken)
         {
             let partial = Self {
                 springs: &self.springs[(self.groups[0] + 1).min(self.springs.len())..],
                 groups: &self.groups[1..],
             };
-            total += partial.count_possible();
+            total = add(total, partial.count_possible(cache));
         }
 
         // Update cache
-        CACHE.lock().unwrap().insert(self.default_hash(), total);
+        cache.insert(self.key(), total);
         total
     }
 
-    fn default_hash(&self) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        self.hash(&mut hasher);
-        hasher.finish()
+    fn key(&self) -> (usize, usize) {
+        (self.springs.len(), self.groups.len())
     }
 }
 
 #[cfg(test)]
 mod test {
+    use proptest::prelude::*;
+
     use super::*;
 
+    fn groups_of(springs: &[char]) -> Vec<usize> {
+        springs
+            .split(|&spring| spring != '#')
+            .map(<[char]>::len)
+            .filter(|&len| len > 0)
+            .collect()
+    }
+
+    // A row with at least one broken spring, where some springs are hidden behind '?'.
+    // The groups come from the row, so there is always at least one arrangement
+    fn record(max_len: usize) -> impl Strategy<Value = (String, Vec<usize>)> {
+        prop::collection::vec((any::<bool>(), any::<bool>()), 1..=max_len)
+            .prop_filter("needs a broken spring", |springs| {
+                springs.iter().any(|&(broken, _)| broken)
+            })
+            .prop_map(|springs| {
+                let row = springs
+                    .iter()
+                    .map(|&(broken, _)| if broken { '#' } else { '.' })
+                    .collect::<Vec<_>>();
+                let hidden = springs
+                    .iter()
+                    .zip(&row)
+                    .map(|(&(_, hidden), &spring)| if hidden { '?' } else { spring })
+                    .collect();
+
+                (hidden, groups_of(&row))
+            })
+    }
+
+    fn record_str(springs: &str, groups: &[usize]) -> String {
+        let groups = groups.iter().map(usize::to_string).collect::<Vec<_>>();
+        format!("{springs} {}", groups.join(","))
+    }
+
+    // Every way of replacing the '?' that matches the groups
+    fn naive_arrangements(springs: &str, groups: &[usize]) -> Vec<Vec<char>> {
+        let springs = springs.chars().collect::<Vec<_>>();
+        let unknowns = springs.iter().filter(|&&spring| spring == '?').count();
+
+        (0..1_usize << unknowns)
+            .map(|arrangement| {
+                let mut bit = 0;
+                springs
+                    .iter()
+                    .map(|&spring| {
+                        if spring != '?' {
+                            return spring;
+                        }
+                        bit += 1;
+                        if arrangement >> (bit - 1) & 1 == 1 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|filled| groups_of(filled) == groups)
+            .collect()
+    }
+
+    fn naive_count(springs: &str, groups: &[usize]) -> usize {
+        naive_arrangements(springs, groups).len()
+    }
+
+    fn forced_str(springs: &str, groups: &[usize]) -> Option<String> {
+        let springs = springs
+            .chars()
+            .map(|c| SpringType::parse(c).unwrap())
+            .collect();
+
+        Record::new(springs, groups.to_vec())
+            .forced()
+            .map(|forced| {
+                forced
+                    .iter()
+                    .map(|spring| match spring {
+                        SpringType::Functional => '.',
+                        SpringType::Broken => '#',
+                        SpringType::Unknown => '?',
+                    })
+                    .collect()
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn forces_what_every_arrangement_agrees_on(
+            springs in "[.#?]{0,12}",
+            groups in prop::collection::vec(1..4_usize, 0..4),
+        ) {
+            let arrangements = naive_arrangements(&springs, &groups);
+            let agreed = arrangements.first().map(|first| {
+                (0..first.len())
+                    .map(|i| {
+                        if arrangements.iter().all(|filled| filled[i] == first[i]) {
+                            first[i]
+                        } else {
+                            '?'
+                        }
+                    })
+                    .collect::<String>()
+            });
+
+            prop_assert_eq!(agreed, forced_str(&springs, &groups));
+        }
+
+        #[test]
+        fn matches_trying_every_arrangement((springs, groups) in record(12)) {
+            let diagram = record_str(&springs, &groups);
+
+            prop_assert_eq!(Some(naive_count(&springs, &groups)), part_one(&diagram));
+        }
+
+        #[test]
+        fn matches_trying_every_unfolded_arrangement((springs, groups) in record(2)) {
+            let diagram = record_str(&springs, &groups);
+            let unfolded_springs = [springs.as_str(); 5].join("?");
+            let unfolded_groups = groups.repeat(5);
+
+            prop_assert_eq!(
+                Some(naive_count(&unfolded_springs, &unfolded_groups)),
+                part_two(&diagram)
+            );
+        }
+    }
+
     #[test]
     fn example() {
         let diagram = "\
@@ -190,21 +466,60 @@ mod test {
         ????.######..#####. 1,6,5
         ?###???????? 3,2,1";
 
-        assert_eq!(21, part_one(diagram));
-        assert_eq!(525152, part_two(diagram));
+        assert_eq!(Some(21), part_one(diagram));
+        assert_eq!(Some(525152), part_two(diagram));
+        assert_eq!(Some(21), part_one_buffered(diagram.as_bytes()).unwrap());
+        assert_eq!(Some(525152), part_two_buffered(diagram.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn forced_springs() {
+        assert_eq!(
+            Some("#.#.###".to_string()),
+            forced_str("???.###", &[1, 1, 3])
+        );
+        assert_eq!(
+            Some(".###.??????".to_string()),
+            forced_str("?###???????", &[3, 2, 1])
+        );
+        assert_eq!(Some("???##???".to_string()), forced_str("????????", &[5]));
+        assert_eq!(Some("...".to_string()), forced_str("?.?", &[]));
+        assert_eq!(None, forced_str("#.#", &[3]));
+    }
+
+    #[test]
+    fn wide_records() {
+        // C(76, 25) ways to place 25 ones in 100 springs, more than a usize holds
+        let springs = "?".repeat(100);
+        let ones = [1; 25];
+        let diagram = record_str(&springs, &ones);
+
+        assert_eq!(None, part_one(&diagram));
+        assert_eq!(None, part_one_buffered(diagram.as_bytes()).unwrap());
+        let records = Day12::parse(&diagram).unwrap();
+        assert_eq!("overflow", Day12::part_one(&records, &mut Explain::off()));
+
+        assert_eq!(Some(springs.clone()), forced_str(&springs, &ones));
+
+        // The first spring's group ends right away, whatever the 24 others do
+        let first = format!("#{}", "?".repeat(119));
+        assert_eq!(
+            Some(format!("#.{}", "?".repeat(118))),
+            forced_str(&first, &ones)
+        );
     }
 
     #[test]
     fn real() {
         let diagram = include_str!("../res/day_12.txt");
 
-        assert_eq!(7674, part_one(diagram));
+        assert_eq!(Some(7674), part_one(diagram));
     }
 
     #[test]
     fn real_part_two() {
         let diagram = include_str!("../res/day_12.txt");
 
-        assert_eq!(4443895258186, part_two(diagram));
+        assert_eq!(Some(4443895258186), part_two(diagram));
     }
 }
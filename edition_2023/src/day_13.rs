@@ -1,29 +1,78 @@
-use crate::common::transpose;
+use crate::common::{
+    bit_grid::BitGrid,
+    explain::Explain,
+    input,
+    parse::{self, ParseError, ParseResult},
+    solution::Solution,
+    Point,
+};
 
 pub fn part_one(patterns: &str) -> usize {
-    solve(patterns, 0)
+    solve(&parse_patterns(patterns).unwrap(), 0)
 }
 
 pub fn part_two(patterns: &str) -> usize {
-    solve(patterns, 1)
+    solve(&parse_patterns(patterns).unwrap(), 1)
 }
 
-fn solve(patterns: &str, target: usize) -> usize {
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Parsed<'a> = Vec<BitGrid>;
+
+    fn parse(patterns: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse_patterns(patterns)
+    }
+
+    fn part_one(patterns: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        solve(patterns, 0).to_string()
+    }
+
+    fn part_two(patterns: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(solve(patterns, 1).to_string())
+    }
+}
+
+// Patterns must be rectangles of '#' and '.', the reflections compare whole rows and columns
+fn parse_patterns(patterns: &str) -> ParseResult<Vec<BitGrid>> {
+    let cell = |c| match c {
+        '#' | '.' => Ok(c),
+        _ => Err(ParseError::new(format!(
+            "Unexpected char {c:?} in a pattern"
+        ))),
+    };
+
+    input::sections(patterns)
+        .into_iter()
+        .map(|pattern| {
+            let pattern = pattern.join("\n");
+            parse::grid(&pattern, cell)?;
+
+            Ok(BitGrid::parse(&pattern, |c| c == '#'))
+        })
+        .collect()
+}
+
+fn solve(patterns: &[BitGrid], target: usize) -> usize {
     patterns
-        .split("\n\n")
-        .flat_map(|pattern| find_symetry(pattern, target))
-        .map(|symetry| symetry.value())
+        .iter()
+        .flat_map(|pattern| {
+            find_grid_reflections(pattern, target)
+                .into_iter()
+                .find(|reflection| reflection.differences == target)
+        })
+        .map(|reflection| reflection.symmetry.value())
         .sum()
 }
 
-#[derive(Debug)]
-enum Symmetry {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Symmetry {
     Vertical(usize),
     Horizontal(usize),
 }
 
 impl Symmetry {
-    fn value(&self) -> usize {
+    pub fn value(&self) -> usize {
         match self {
             Self::Vertical(col) => *col,
             Self::Horizontal(line) => line * 100,
@@ -31,49 +80,76 @@ impl Symmetry {
     }
 }
 
-type Matrix = Vec<Vec<char>>;
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Reflection {
+    pub symmetry: Symmetry,
+    pub differences: usize,
+    // Cell (x = column, y = line) that has to flip for the reflection to be perfect.
+    // Only known when there is exactly one difference
+    pub smudge: Option<Point>,
+}
 
-fn find_symetry(pattern: &str, target: usize) -> Option<Symmetry> {
-    let grid = pattern
-        .lines()
-        .map(|line| line.trim().chars().collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+// Every axis with at most `max_smudges` differences. Horizontal axes come first
+pub fn find_reflections(pattern: &str, max_smudges: usize) -> Vec<Reflection> {
+    find_grid_reflections(&BitGrid::parse(pattern, |c| c == '#'), max_smudges)
+}
+
+pub fn find_grid_reflections(grid: &BitGrid, max_smudges: usize) -> Vec<Reflection> {
+    let horizontal = find_horizontal_reflections(grid, max_smudges)
+        .into_iter()
+        .map(|(line, differences, smudge)| Reflection {
+            symmetry: Symmetry::Horizontal(line),
+            differences,
+            smudge: smudge.map(|(y, x)| Point::new_usize(x, y)),
+        });
 
-    find_horizontal_symetry(&grid, target)
-        .map(Symmetry::Horizontal)
-        .or_else(|| find_horizontal_symetry(&transpose(grid), target).map(Symmetry::Vertical))
+    let vertical = find_horizontal_reflections(&grid.transpose(), max_smudges)
+        .into_iter()
+        .map(|(col, differences, smudge)| Reflection {
+            symmetry: Symmetry::Vertical(col),
+            differences,
+            smudge: smudge.map(|(x, y)| Point::new_usize(x, y)),
+        });
+
+    horizontal.chain(vertical).collect()
 }
 
-fn find_horizontal_symetry(matrix: &Matrix, target: usize) -> Option<usize> {
-    for line in 1..matrix.len() {
+// (line, differences, (line, column) of the single difference)
+type LineReflection = (usize, usize, Option<(usize, usize)>);
+
+fn find_horizontal_reflections(grid: &BitGrid, max_differences: usize) -> Vec<LineReflection> {
+    let mut reflections = Vec::new();
+
+    for line in 1..grid.height() {
         let mut differences = 0;
+        let mut smudge = None;
 
         for index in 0..line {
             let left_index = line + index;
-            if left_index >= matrix.len() {
+            if left_index >= grid.height() {
                 break;
             }
 
-            differences += count_diferences(&matrix[line - index - 1], &matrix[left_index]);
-            if differences > target {
+            let upper = line - index - 1;
+            let found = grid.row_differences(upper, left_index);
+            if found == 1 && differences == 0 {
+                smudge = grid
+                    .first_row_difference(upper, left_index)
+                    .map(|column| (upper, column));
+            }
+
+            differences += found;
+            if differences > max_differences {
                 break;
             }
         }
 
-        if differences == target {
-            return Some(line);
+        if differences <= max_differences {
+            reflections.push((line, differences, smudge.filter(|_| differences == 1)));
         }
     }
 
-    None
-}
-
-fn count_diferences(list_a: &[char], list_b: &[char]) -> usize {
-    list_a
-        .iter()
-        .zip(list_b.iter())
-        .map(|(a, b)| if a == b { 0 } else { 1 })
-        .sum()
+    reflections
 }
 
 #[cfg(test)]
@@ -101,6 +177,85 @@ mod tests {
 
         assert_eq!(405, part_one(grid));
         assert_eq!(400, part_two(grid));
+
+        let windows = grid.replace('\n', "\r\n");
+        assert_eq!(405, part_one(&windows));
+        assert_eq!(400, part_two(&windows));
+    }
+
+    #[test]
+    fn reflections() {
+        let pattern = "\
+        #.##..##.
+        ..#.##.#.
+        ##......#
+        ##......#
+        ..#.##.#.
+        ..##..##.
+        #.#.##.#.";
+
+        let reflections = find_reflections(pattern, 1);
+
+        assert_eq!(
+            vec![
+                Reflection {
+                    symmetry: Symmetry::Horizontal(3),
+                    differences: 1,
+                    smudge: Some(Point::new(0, 0)),
+                },
+                Reflection {
+                    symmetry: Symmetry::Vertical(5),
+                    differences: 0,
+                    smudge: None,
+                },
+            ],
+            reflections
+        );
+
+        let pattern = "\
+        #...##..#
+        #....#..#
+        ..##..###
+        #####.##.
+        #####.##.
+        ..##..###
+        #....#..#";
+
+        let reflections = find_reflections(pattern, 1);
+
+        assert_eq!(
+            vec![
+                Reflection {
+                    symmetry: Symmetry::Horizontal(1),
+                    differences: 1,
+                    smudge: Some(Point::new(4, 0)),
+                },
+                Reflection {
+                    symmetry: Symmetry::Horizontal(4),
+                    differences: 0,
+                    smudge: None,
+                },
+            ],
+            reflections
+        );
+    }
+
+    #[test]
+    fn real_has_single_answers() {
+        let grid = include_str!("../res/day_13.txt");
+
+        for pattern in input::sections(grid) {
+            let pattern = pattern.join("\n");
+
+            for smudges in 0..=1 {
+                let answers = find_reflections(&pattern, smudges)
+                    .into_iter()
+                    .filter(|reflection| reflection.differences == smudges)
+                    .count();
+
+                assert_eq!(1, answers, "{pattern}");
+            }
+        }
     }
 
     #[test]
@@ -1,105 +1,143 @@
-use std::{
-    collections::{hash_map::DefaultHasher, HashSet},
-    hash::{Hash, Hasher},
+use crate::common::{
+    cycle::find_cycle,
+    explain::Explain,
+    parse::{self, ParseError, ParseResult},
+    solution::Solution,
 };
 
-use crate::common::turn_anticlock;
-
 pub fn part_one(rocks_str: &str) -> usize {
-    let mut rocks = parse(rocks_str);
-    fall_north(&mut rocks);
-
-    calculate_value(&rocks)
+    north_load(parse(rocks_str).unwrap())
 }
 
 pub fn part_two(rocks_str: &str) -> usize {
-    let mut rocks = parse(rocks_str);
-    let mut sequence = Vec::new();
-    let mut hashes = Vec::new();
+    north_load_after_spins(parse(rocks_str).unwrap())
+}
 
-    let loops = 1000000000;
+pub struct Day14;
 
-    for index in 0..=loops {
-        for _ in 0..4 {
-            fall_north(&mut rocks);
-            rocks = turn_anticlock(rocks);
-        }
-        let last_value = calculate_value(&rocks);
-        sequence.push(last_value);
-        let mut hasher = DefaultHasher::new();
-        rocks.hash(&mut hasher);
-        let hash = hasher.finish();
-
-        if let Some((repetition, _)) = hashes.iter().rev().enumerate().find(|(_, h)| **h == hash) {
-            let repetition_size = repetition + 1;
-            let true_size = loops - (index - repetition_size);
-            let seq_index = true_size % repetition_size;
-
-            return sequence[index - repetition_size + seq_index - 1];
-        }
+impl Solution for Day14 {
+    type Parsed<'a> = Vec<Vec<char>>;
+
+    fn parse(rocks_str: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse(rocks_str)
+    }
 
-        hashes.push(hash);
+    fn part_one(rocks: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        north_load(rocks.clone()).to_string()
     }
 
-    *sequence.last().unwrap()
+    fn part_two(rocks: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(north_load_after_spins(rocks.clone()).to_string())
+    }
 }
 
-fn parse(rocks_str: &str) -> Vec<Vec<char>> {
-    rocks_str
-        .lines()
-        .map(|line| line.trim().chars().collect())
-        .collect()
+fn north_load(mut rocks: Vec<Vec<char>>) -> usize {
+    tilt(&mut rocks, Direction::North);
+
+    calculate_load(&rocks, Direction::North)
 }
 
-fn fall_north(rocks: &mut [Vec<char>]) {
-    for j in 0..rocks[0].len() {
-        let mut new_rock_position = 0;
-        let mut new_column = HashSet::new();
-        let mut immovebles = HashSet::new();
-
-        for (i, line) in rocks.iter().enumerate() {
-            let rock = line[j];
-
-            if rock == 'O' {
-                new_column.insert(new_rock_position);
-                new_rock_position += 1;
-            } else if rock == '#' {
-                immovebles.insert(i);
-                new_rock_position = i + 1;
-            }
-        }
+fn north_load_after_spins(rocks: Vec<Vec<char>>) -> usize {
+    let cycle = find_cycle(rocks.clone(), |rocks| {
+        let mut rocks = rocks.clone();
+        spin(&mut rocks);
+        rocks
+    });
+
+    let mut rocks = rocks;
+    for _ in 0..cycle.equivalent_steps(1_000_000_000) {
+        spin(&mut rocks);
+    }
+
+    calculate_load(&rocks, Direction::North)
+}
 
-        for (i, line) in rocks.iter_mut().enumerate() {
-            if new_column.contains(&i) {
-                line[j] = 'O';
-            } else if immovebles.contains(&i) {
-                line[j] = '#';
-            } else {
-                line[j] = '.';
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
+
+pub fn parse(rocks_str: &str) -> ParseResult<Vec<Vec<char>>> {
+    parse::grid(rocks_str, |c| match c {
+        'O' | '#' | '.' => Ok(c),
+        _ => Err(ParseError::new(format!(
+            "Unexpected char {c:?} in the platform"
+        ))),
+    })
+}
+
+fn spin(rocks: &mut [Vec<char>]) {
+    for direction in [
+        Direction::North,
+        Direction::West,
+        Direction::South,
+        Direction::East,
+    ] {
+        tilt(rocks, direction);
+    }
+}
+
+pub fn tilt(rocks: &mut [Vec<char>], direction: Direction) {
+    let rows = rocks.len();
+    let cols = rocks.first().map_or(0, Vec::len);
+
+    // Every lane is walked starting from the edge the rocks roll towards
+    let (lanes, lane_len) = match direction {
+        Direction::North | Direction::South => (cols, rows),
+        Direction::West | Direction::East => (rows, cols),
+    };
+    let position = |lane: usize, step: usize| match direction {
+        Direction::North => (step, lane),
+        Direction::South => (rows - 1 - step, lane),
+        Direction::West => (lane, step),
+        Direction::East => (lane, cols - 1 - step),
+    };
+
+    for lane in 0..lanes {
+        let mut free = 0;
+
+        for step in 0..lane_len {
+            let (i, j) = position(lane, step);
+
+            match rocks[i][j] {
+                'O' => {
+                    rocks[i][j] = '.';
+                    let (free_i, free_j) = position(lane, free);
+                    rocks[free_i][free_j] = 'O';
+                    free += 1;
+                }
+                '#' => free = step + 1,
+                _ => (),
             }
         }
     }
 }
 
-fn calculate_value(rocks: &[Vec<char>]) -> usize {
+pub fn calculate_load(rocks: &[Vec<char>], direction: Direction) -> usize {
+    let rows = rocks.len();
+    let cols = rocks.first().map_or(0, Vec::len);
+
     rocks
         .iter()
-        .rev()
         .enumerate()
-        .map(|(i, line)| (i + 1) * line.iter().filter(|&&c| c == 'O').count())
+        .flat_map(|(i, line)| {
+            line.iter()
+                .enumerate()
+                .filter(|(_, &c)| c == 'O')
+                .map(move |(j, _)| (i, j))
+        })
+        .map(|(i, j)| match direction {
+            Direction::North => rows - i,
+            Direction::South => i + 1,
+            Direction::West => cols - j,
+            Direction::East => j + 1,
+        })
         .sum()
 }
 
-#[allow(dead_code)]
-fn print_rocks(rocks: &[Vec<char>]) {
-    for line in rocks {
-        for char in line {
-            print!("{char}");
-        }
-        println!();
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,21 +156,51 @@ mod tests {
         #....###..
         #OO..#....";
 
-        let mut rocks = parse(rocks_str);
-        fall_north(&mut rocks);
+        let mut rocks = parse(rocks_str).unwrap();
+        tilt(&mut rocks, Direction::North);
 
-        assert_eq!(136, calculate_value(&rocks));
+        assert_eq!(136, calculate_load(&rocks, Direction::North));
+        assert_eq!(136, part_one(rocks_str));
         assert_eq!(64, part_two(rocks_str));
     }
 
+    #[test]
+    fn tilt_all_directions() {
+        let rocks_str = "\
+        O.#.
+        .O..
+        ..O#
+        #..O";
+
+        let mut rocks = parse(rocks_str).unwrap();
+        tilt(&mut rocks, Direction::West);
+        assert_eq!(parse("O.#.\nO...\nO..#\n#O..").unwrap(), rocks);
+        assert_eq!(4 + 4 + 4 + 3, calculate_load(&rocks, Direction::West));
+
+        let mut rocks = parse(rocks_str).unwrap();
+        tilt(&mut rocks, Direction::East);
+        assert_eq!(parse(".O#.\n...O\n..O#\n#..O").unwrap(), rocks);
+        assert_eq!(2 + 4 + 3 + 4, calculate_load(&rocks, Direction::East));
+
+        let mut rocks = parse(rocks_str).unwrap();
+        tilt(&mut rocks, Direction::South);
+        assert_eq!(parse("..#.\n....\nO..#\n#OOO").unwrap(), rocks);
+        assert_eq!(3 + 4 + 4 + 4, calculate_load(&rocks, Direction::South));
+
+        let mut rocks = parse(rocks_str).unwrap();
+        tilt(&mut rocks, Direction::North);
+        assert_eq!(parse("OO#.\n..O.\n...#\n#..O").unwrap(), rocks);
+        assert_eq!(4 + 4 + 3 + 1, calculate_load(&rocks, Direction::North));
+    }
+
     #[test]
     fn real() {
         let rocks_str = include_str!("../res/day_14.txt");
 
-        let mut rocks = parse(rocks_str);
-        fall_north(&mut rocks);
+        let mut rocks = parse(rocks_str).unwrap();
+        tilt(&mut rocks, Direction::North);
 
-        assert_eq!(110128, calculate_value(&rocks));
+        assert_eq!(110128, calculate_load(&rocks, Direction::North));
         assert_eq!(103861, part_two(rocks_str));
     }
 }
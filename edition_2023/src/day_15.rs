@@ -1,137 +1,239 @@
+use std::{
+    borrow::Borrow,
+    hash::{BuildHasher, BuildHasherDefault, Hasher},
+};
+
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseError, ParseResult},
+    solution::{or_overflow, Solution},
+};
+
 pub fn part_one(input: &str) -> usize {
-    input.split(',').map(hash).map(|byte| byte as usize).sum()
+    hash_steps(steps(input))
+}
+
+pub fn part_two(input: &str) -> Option<usize> {
+    focusing_power(steps(input))
 }
 
-pub fn part_two(input: &str) -> usize {
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Parsed<'a> = Vec<&'a str>;
+
+    // Part one hashes the raw steps, but they have to be valid commands for part two
+    fn parse(input: &str) -> ParseResult<Self::Parsed<'_>> {
+        steps(input)
+            .map(|step| Command::parse(step).map(|_| step))
+            .collect()
+    }
+
+    fn part_one(steps: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        hash_steps(steps.iter().copied()).to_string()
+    }
+
+    fn part_two(steps: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(or_overflow(focusing_power(steps.iter().copied())))
+    }
+}
+
+fn hash_steps<'a>(steps: impl Iterator<Item = &'a str>) -> usize {
+    steps.map(hash).map(|byte| byte as usize).sum()
+}
+
+fn focusing_power<'a>(steps: impl Iterator<Item = &'a str>) -> Option<usize> {
     let mut deer_hash_map = DeerHashMap::new();
 
-    input
-        .split(',')
-        .map(Command::parse)
+    steps
+        .map(|step| Command::parse(step).unwrap())
         .for_each(|command| deer_hash_map.apply(command));
 
     deer_hash_map.calculate_power()
 }
 
-#[derive(Debug)]
-enum Command {
+// The sequence may be wrapped in several lines, which are ignored
+fn steps(sequence: &str) -> impl Iterator<Item = &str> {
+    input::lines(sequence)
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
     Minus { label: String },
     Equals { label: String, focal_length: usize },
 }
 
 impl Command {
-    fn parse(cmd: &str) -> Self {
-        if cmd.contains('=') {
-            let mut parts = cmd.trim().split('=');
-
-            return Self::Equals {
-                label: parts.next().unwrap().to_owned(),
-                focal_length: parts.next().unwrap().parse().unwrap(),
-            };
-        } else if cmd.ends_with('-') {
-            return Self::Minus {
-                label: cmd.trim().replace('-', ""),
-            };
+    pub fn parse(cmd: &str) -> ParseResult<Self> {
+        if let Some((label, focal_length)) = cmd.split_once('=') {
+            return Ok(Self::Equals {
+                label: label.to_owned(),
+                focal_length: number(focal_length)?,
+            });
+        } else if let Some(label) = cmd.strip_suffix('-') {
+            return Ok(Self::Minus {
+                label: label.to_owned(),
+            });
         }
 
-        panic!("Unknown command {cmd}");
+        Err(ParseError::new(format!("Unknown command {cmd}")))
     }
 }
 
-#[derive(Debug)]
-struct Lens {
-    label: String,
-    focal_length: usize,
+// The Holiday ASCII String Helper, for any hashing that std does. Beware that `Hash` for strings
+// writes a 0xff after the bytes, so a `HashMap<String, _, BuildHolidayHasher>` puts the labels
+// in other boxes than the puzzle does
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HolidayHasher {
+    state: u8,
 }
 
-#[derive(Debug)]
-struct LensBox {
-    lenses: Vec<Lens>,
-}
+impl Hasher for HolidayHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.state = bytes.iter().fold(self.state, |acc, &cur| {
+            let acc = acc as u64 + cur as u64;
+            ((acc * 17) % 256) as u8
+        });
+    }
 
-impl LensBox {
-    fn find_lens(&self, label: &str) -> Option<(usize, &Lens)> {
-        self.lenses
-            .iter()
-            .enumerate()
-            .find(|(_, l)| l.label == label)
+    fn finish(&self) -> u64 {
+        self.state as u64
     }
 }
 
-#[derive(Debug)]
-struct DeerHashMap {
-    lens_boxes: Vec<LensBox>,
+pub type BuildHolidayHasher = BuildHasherDefault<HolidayHasher>;
+
+pub fn hash(string: &str) -> u8 {
+    let mut hasher = HolidayHasher::default();
+    hasher.write(string.as_bytes());
+
+    hasher.state
 }
 
-impl DeerHashMap {
-    fn new() -> Self {
-        let mut boxes = Vec::with_capacity(256);
+// The lens boxes: 256 buckets picked by the HASH of the key's bytes, each keeping its entries
+// in the order they came in. Replacing a value keeps its place
+#[derive(Debug, Clone)]
+pub struct DeerHashMap<K, V> {
+    boxes: Vec<Vec<(K, V)>>,
+    len: usize,
+}
+
+impl<K: AsRef<[u8]> + Eq, V> DeerHashMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            boxes: (0..256).map(|_| Vec::new()).collect(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn box_number<Q: AsRef<[u8]> + ?Sized>(key: &Q) -> usize {
+        let mut hasher = BuildHolidayHasher::default().build_hasher();
+        hasher.write(key.as_ref());
+
+        hasher.finish() as usize
+    }
 
-        for _ in 0..256 {
-            boxes.push(LensBox { lenses: Vec::new() });
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let lens_box = &mut self.boxes[Self::box_number(&key)];
+
+        if let Some((_, old)) = lens_box.iter_mut().find(|(k, _)| *k == key) {
+            return Some(std::mem::replace(old, value));
         }
 
-        Self { lens_boxes: boxes }
+        lens_box.push((key, value));
+        self.len += 1;
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: AsRef<[u8]> + Eq + ?Sized,
+    {
+        let lens_box = &mut self.boxes[Self::box_number(key)];
+        let index = lens_box.iter().position(|(k, _)| k.borrow() == key)?;
+
+        self.len -= 1;
+        Some(lens_box.remove(index).1)
     }
 
-    fn calculate_power(&self) -> usize {
-        self.lens_boxes
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: AsRef<[u8]> + Eq + ?Sized,
+    {
+        self.boxes[Self::box_number(key)]
             .iter()
-            .enumerate()
-            .fold(0, |acc, (box_number, lens_box)| {
-                let partial = lens_box
-                    .lenses
-                    .iter()
-                    .enumerate()
-                    .fold(0, |acc, (i, lens)| acc + (i + 1) * lens.focal_length);
-
-                acc + (box_number + 1) * partial
-            })
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, v)| v)
+    }
+
+    // Box by box, and in each box from the front
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.boxes
+            .iter()
+            .flat_map(|lens_box| lens_box.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+impl<K: AsRef<[u8]> + Eq, V> Default for DeerHashMap<K, V> {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn apply(&mut self, command: Command) {
+impl DeerHashMap<String, usize> {
+    pub fn apply(&mut self, command: Command) {
         match command {
             Command::Minus { label } => {
-                let lens_box = self.get_box(&label);
-
-                if let Some((index, _)) = lens_box.find_lens(&label) {
-                    lens_box.lenses.remove(index);
-                }
+                self.remove(label.as_str());
             }
             Command::Equals {
                 label,
                 focal_length,
             } => {
-                let lens = Lens {
-                    label,
-                    focal_length,
-                };
-
-                let lens_box = self.get_box(&lens.label);
-
-                if let Some((index, _)) = lens_box.find_lens(&lens.label) {
-                    let _ = std::mem::replace(&mut lens_box.lenses[index], lens);
-                } else {
-                    lens_box.lenses.push(lens);
-                }
+                self.insert(label, focal_length);
             }
         }
     }
-
-    fn get_box(&mut self, label: &str) -> &mut LensBox {
-        &mut self.lens_boxes[hash(label) as usize]
-    }
 }
 
-fn hash(string: &str) -> u8 {
-    string.as_bytes().iter().fold(0, |acc, &cur| {
-        let acc = acc as u64 + cur as u64;
-        ((acc * 17) % 256) as u8
-    })
+impl<K> DeerHashMap<K, usize> {
+    // None when it doesn't fit
+    pub fn calculate_power(&self) -> Option<usize> {
+        self.boxes
+            .iter()
+            .enumerate()
+            .try_fold(0_usize, |acc, (box_number, lens_box)| {
+                let partial = lens_box.iter().enumerate().try_fold(
+                    0_usize,
+                    |acc, (i, (_, focal_length))| {
+                        acc.checked_add((i + 1).checked_mul(*focal_length)?)
+                    },
+                )?;
+
+                acc.checked_add((box_number + 1).checked_mul(partial)?)
+            })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
     use super::*;
 
     #[test]
@@ -141,13 +243,90 @@ mod tests {
         let commands = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
 
         assert_eq!(1320, part_one(commands));
-        assert_eq!(145, part_two(commands));
+        assert_eq!(Some(145), part_two(commands));
+        assert_eq!(1320, part_one(&format!("\u{feff}{commands}\r\n")));
+        assert_eq!(Some(145), part_two(&format!("{commands}\n")));
+    }
+
+    #[test]
+    fn overflow() {
+        // rn and cm go to the first box, qp to the second
+        assert_eq!(Some(usize::MAX), part_two(&format!("rn={}", usize::MAX)));
+        assert_eq!(None, part_two(&format!("rn={},cm=1", usize::MAX)));
+        assert_eq!(None, part_two(&format!("qp={}", usize::MAX / 2 + 1)));
+
+        let steps = Day15::parse("cm=1,rn=9223372036854775808").unwrap();
+        assert_eq!(
+            "overflow",
+            Day15::part_two(&steps, &mut Explain::off()).unwrap()
+        );
+    }
+
+    #[test]
+    fn as_a_map() {
+        let mut map = DeerHashMap::new();
+        "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7"
+            .split(',')
+            .for_each(|step| map.apply(Command::parse(step).unwrap()));
+
+        let lenses = map
+            .iter()
+            .map(|(k, &v)| (k.as_str(), v))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![("rn", 1), ("cm", 2), ("ot", 7), ("ab", 5), ("pc", 6)],
+            lenses
+        );
+        assert_eq!(Some(&7), map.get("ot"));
+        assert_eq!(None, map.get("qp"));
+        assert_eq!(Some(2), map.remove("cm"));
+        assert_eq!(4, map.len());
+
+        let mut bytes = DeerHashMap::new();
+        assert_eq!(None, bytes.insert(vec![b'H', b'A'], 'x'));
+        assert_eq!(Some('x'), bytes.insert(vec![b'H', b'A'], 'y'));
+        assert_eq!(Some(&'y'), bytes.get(&[b'H', b'A'][..]));
+    }
+
+    #[test]
+    fn with_std() {
+        let mut hasher = BuildHolidayHasher::default().build_hasher();
+        hasher.write(b"HA");
+        hasher.write(b"SH");
+        assert_eq!(52, hasher.finish());
+
+        let mut map = HashMap::with_hasher(BuildHolidayHasher::default());
+        map.insert("rn", 1);
+        map.insert("cm", 2);
+        assert_eq!(Some(&2), map.get("cm"));
+    }
+
+    proptest! {
+        #[test]
+        fn matches_std_hash_map(steps in prop::collection::vec(("[a-d]{1,3}", prop::option::of(0..10_usize)), 0..64)) {
+            let mut deer = DeerHashMap::new();
+            let mut expected = HashMap::new();
+
+            for (label, focal_length) in steps {
+                match focal_length {
+                    Some(focal_length) => {
+                        prop_assert_eq!(expected.insert(label.clone(), focal_length), deer.insert(label, focal_length));
+                    }
+                    None => prop_assert_eq!(expected.remove(&label), deer.remove(&label)),
+                }
+            }
+
+            prop_assert_eq!(expected.len(), deer.len());
+            for (label, focal_length) in &expected {
+                prop_assert_eq!(Some(focal_length), deer.get(label));
+            }
+        }
     }
 
     #[test]
     fn real() {
         let commands = include_str!("../res/day_15.txt");
         assert_eq!(501680, part_one(commands));
-        assert_eq!(241094, part_two(commands));
+        assert_eq!(Some(241094), part_two(commands));
     }
 }
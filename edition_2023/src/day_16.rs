@@ -1,14 +1,44 @@
 use std::{char, collections::HashSet};
 
-pub fn part_one(input: &str) -> usize {
-    let mut map = parse(input);
-    cast_rays(&mut map, 0, 0, RayDirection::Rightward);
+use crate::common::{
+    explain::Explain,
+    parse::{self, ParseError, ParseResult},
+    solution::Solution,
+};
 
-    count_energized(&map)
+pub fn part_one(input: &str) -> usize {
+    energized_from_corner(&mut parse(input).unwrap())
 }
 
 pub fn part_two(input: &str) -> usize {
-    let mut map = parse(input);
+    most_energized(&mut parse(input).unwrap())
+}
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    type Parsed<'a> = Vec<Vec<Tile>>;
+
+    fn parse(input: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part_one(map: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        energized_from_corner(&mut map.clone()).to_string()
+    }
+
+    fn part_two(map: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(most_energized(&mut map.clone()).to_string())
+    }
+}
+
+fn energized_from_corner(map: &mut [Vec<Tile>]) -> usize {
+    cast_rays(map, 0, 0, RayDirection::Rightward);
+
+    count_energized(map)
+}
+
+fn most_energized(map: &mut [Vec<Tile>]) -> usize {
     let mut max = 0;
 
     let rows = map.len();
@@ -24,12 +54,12 @@ pub fn part_two(input: &str) -> usize {
     for (direction, (row_range, col_range)) in directions {
         for i in row_range {
             for j in col_range.clone() {
-                cast_rays(&mut map, i, j, direction.clone());
-                let result = count_energized(&map);
+                cast_rays(map, i, j, direction.clone());
+                let result = count_energized(map);
                 if result > max {
                     max = result;
                 }
-                reset_rays(&mut map);
+                reset_rays(map);
             }
         }
     }
@@ -56,7 +86,7 @@ impl RayDirection {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 enum MirrorType {
     Foward,
     Back,
@@ -72,7 +102,7 @@ impl MirrorType {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 enum SplitterType {
     Horizontal,
     Vertical,
@@ -88,7 +118,7 @@ impl SplitterType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum TileType {
     Empty,
     Mirror(MirrorType),
@@ -96,40 +126,39 @@ enum TileType {
 }
 
 impl TileType {
-    fn parse(char: char) -> Self {
+    fn parse(char: char) -> ParseResult<Self> {
         if let Some(mirror) = MirrorType::parse(char) {
-            return Self::Mirror(mirror);
+            return Ok(Self::Mirror(mirror));
         }
 
         if let Some(splitter) = SplitterType::parse(char) {
-            return Self::Splitter(splitter);
+            return Ok(Self::Splitter(splitter));
         }
 
-        Self::Empty
+        match char {
+            '.' => Ok(Self::Empty),
+            _ => Err(ParseError::new(format!("Unknown tile {char:?}"))),
+        }
     }
 }
 
-#[derive(Debug)]
-struct Tile {
+#[derive(Debug, Clone)]
+pub struct Tile {
     rays: HashSet<RayDirection>,
     kind: TileType,
 }
 
 impl Tile {
-    fn parse(char: char) -> Self {
-        Self {
+    fn parse(char: char) -> ParseResult<Self> {
+        Ok(Self {
             rays: HashSet::new(),
-            kind: TileType::parse(char),
-        }
+            kind: TileType::parse(char)?,
+        })
     }
 }
 
-fn parse(input: &str) -> Vec<Vec<Tile>> {
-    input
-        .trim()
-        .lines()
-        .map(|line| line.trim().chars().map(Tile::parse).collect())
-        .collect()
+fn parse(contraption: &str) -> ParseResult<Vec<Vec<Tile>>> {
+    parse::grid(contraption, Tile::parse)
 }
 
 fn cast_rays(
@@ -228,7 +257,7 @@ mod tests {
         .|....-|.\
         ..//.|...."#;
 
-        let mut map = parse(input);
+        let mut map = parse(input).unwrap();
         cast_rays(&mut map, 0, 0, RayDirection::Rightward);
         assert_eq!(46, part_one(input));
         assert_eq!(51, part_two(input));
@@ -3,10 +3,40 @@ use std::{
     collections::{BinaryHeap, HashSet},
 };
 
-use crate::common::Point;
+use crate::common::{
+    explain::Explain,
+    parse::{self, ParseError, ParseResult},
+    solution::Solution,
+    Point,
+};
 
 pub fn part_one(heat_map: &str) -> u32 {
-    let mut grid = parse_heatmap(heat_map);
+    least_heat_loss(parse_heatmap(heat_map).unwrap())
+}
+
+pub fn part_two(heat_map: &str) -> u32 {
+    least_heat_loss_ultra(parse_heatmap(heat_map).unwrap())
+}
+
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Parsed<'a> = Vec<Vec<Node>>;
+
+    fn parse(heat_map: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse_heatmap(heat_map)
+    }
+
+    fn part_one(grid: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        least_heat_loss(grid.clone()).to_string()
+    }
+
+    fn part_two(grid: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(least_heat_loss_ultra(grid.clone()).to_string())
+    }
+}
+
+fn least_heat_loss(mut grid: Vec<Vec<Node>>) -> u32 {
     dijkstra(&mut grid, |_, _, next| next.times_in_direction <= 3);
 
     let lines = grid.len() - 1;
@@ -15,8 +45,8 @@ pub fn part_one(heat_map: &str) -> u32 {
     grid[lines][columns].distance.unwrap_or(u32::MAX)
 }
 
-pub fn part_two(heat_map: &str) -> u32 {
-    let mut grid = parse_heatmap(heat_map);
+// Ultra crucibles move 4 to 10 blocks before turning
+fn least_heat_loss_ultra(mut grid: Vec<Vec<Node>>) -> u32 {
     dijkstra(&mut grid, |last_direction, times_in_direction, next| {
         if next.times_in_direction > 10 {
             return false;
@@ -38,8 +68,8 @@ pub fn part_two(heat_map: &str) -> u32 {
     grid[lines][columns].distance.unwrap_or(u32::MAX)
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct Node {
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Node {
     position: Point,
     heat_diss: u32,
     distance: Option<u32>,
@@ -122,20 +152,25 @@ impl PartialOrd for State {
     }
 }
 
-fn parse_heatmap(grid: &str) -> Vec<Vec<Node>> {
-    grid.lines()
+fn parse_heatmap(grid: &str) -> ParseResult<Vec<Vec<Node>>> {
+    let heat_losses = parse::grid(grid, |c| {
+        c.to_digit(10)
+            .ok_or_else(|| ParseError::new(format!("Invalid heat loss {c:?}")))
+    })?;
+
+    Ok(heat_losses
+        .into_iter()
         .enumerate()
         .map(|(y, line)| {
-            line.trim()
-                .chars()
+            line.into_iter()
                 .enumerate()
-                .map(|(x, c)| Node::new(x, y, c.to_digit(10).unwrap()))
+                .map(|(x, heat_loss)| Node::new(x, y, heat_loss))
                 .collect()
         })
-        .collect()
+        .collect())
 }
 
-fn dijkstra<F>(grid: &mut Vec<Vec<Node>>, accep_next: F)
+fn dijkstra<F>(grid: &mut [Vec<Node>], accep_next: F)
 where
     F: Fn(&Direction, usize, &State) -> bool,
 {
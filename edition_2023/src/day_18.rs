@@ -1,69 +1,372 @@
-use crate::common::Point;
+use std::{
+    collections::HashSet,
+    fmt,
+    fmt::Write,
+    io::{self, BufRead},
+};
+
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseError, ParseResult, Required},
+    solution::Solution,
+    stream::try_fold_lines,
+    Point,
+};
 
 pub fn part_one(dig_plan: &str) -> usize {
-    calculare_area(dig_plan, |direction, length, _| DigPlanLine {
-        direction,
-        length,
-    })
+    DigPlan::from_directions(dig_plan).unwrap().lagoon_size()
 }
 
 pub fn part_two(dig_plan: &str) -> usize {
-    calculare_area(dig_plan, |_, _, color| {
-        let length_str = &color[2..color.len() - 2];
-        let direction_char = color.chars().nth(color.len() - 2).unwrap();
+    DigPlan::from_colors(dig_plan).unwrap().lagoon_size()
+}
+
+pub fn part_one_buffered(reader: impl BufRead) -> io::Result<usize> {
+    calculate_area_buffered(reader, plan_part_one)
+}
+
+pub fn part_two_buffered(reader: impl BufRead) -> io::Result<usize> {
+    calculate_area_buffered(reader, plan_part_two)
+}
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    // The plan as read by each part
+    type Parsed<'a> = (DigPlan, DigPlan);
+
+    fn parse(dig_plan: &str) -> ParseResult<Self::Parsed<'_>> {
+        Ok((
+            DigPlan::from_directions(dig_plan)?,
+            DigPlan::from_colors(dig_plan)?,
+        ))
+    }
+
+    fn part_one((plan, _): &Self::Parsed<'_>, explain: &mut Explain) -> String {
+        explain_plan(plan, explain);
+        plan.lagoon_size().to_string()
+    }
+
+    fn part_two((_, plan): &Self::Parsed<'_>, explain: &mut Explain) -> Option<String> {
+        explain_plan(plan, explain);
+        Some(plan.lagoon_size().to_string())
+    }
+}
+
+fn explain_plan(plan: &DigPlan, explain: &mut Explain) {
+    explain.step("plan", || {
+        format!(
+            "{} edges, perimeter {}, interior {}",
+            plan.lines.len(),
+            plan.perimeter(),
+            plan.interior()
+        )
+    });
+}
 
-        let direction = Direction::parse_part_two(direction_char);
+// Reads the direction and length from the line's direction and length, or from its color, as
+// each part does
+type PlanBuilder = fn(&str, &str, &Color, &str) -> ParseResult<(Direction, usize)>;
 
-        DigPlanLine {
-            direction,
-            length: usize::from_str_radix(length_str, 16).unwrap(),
+fn plan_part_one(
+    direction: &str,
+    length: &str,
+    _: &Color,
+    _: &str,
+) -> ParseResult<(Direction, usize)> {
+    let direction = match direction {
+        "U" => Direction::Up,
+        "D" => Direction::Down,
+        "R" => Direction::Right,
+        "L" => Direction::Left,
+        _ => return Err(ParseError::new(format!("Unexpected direction {direction}"))),
+    };
+
+    Ok((direction, number(length)?))
+}
+
+// (#70c710): the length in 5 hex digits, then the direction
+fn plan_part_two(
+    _: &str,
+    _: &str,
+    color: &Color,
+    color_str: &str,
+) -> ParseResult<(Direction, usize)> {
+    let hex = color.hex();
+    let length = hex >> 4;
+
+    let direction = match hex & 0xf {
+        0 => Direction::Right,
+        1 => Direction::Down,
+        2 => Direction::Left,
+        3 => Direction::Up,
+        _ => {
+            return Err(ParseError::new(format!(
+                "Invalid direction in color {color_str}"
+            )))
         }
+    };
+
+    Ok((direction, length as usize))
+}
+
+fn parse_line(line: &str, plan_builder: PlanBuilder) -> ParseResult<DigPlanLine> {
+    let mut parts = line.split_whitespace();
+    let direction = parts.next().required("the direction")?;
+    let length = parts.next().required("the length")?;
+    let color_str = parts.next().required("the color")?;
+
+    let color = Color::parse(color_str)?;
+    let (direction, length) = plan_builder(direction, length, &color, color_str)?;
+
+    Ok(DigPlanLine {
+        direction,
+        length,
+        color,
     })
 }
 
-fn calculare_area<F>(dig_plan: &str, plan_builder: F) -> usize
-where
-    F: Fn(Direction, usize, String) -> DigPlanLine,
-{
-    let plans: Vec<DigPlanLine> = dig_plan
-        .lines()
-        .map(|line| {
-            let parts: Vec<_> = line.trim().split(' ').collect();
-            let direction = Direction::parse(parts[0].chars().next().unwrap());
-            let color = String::from(parts[2]);
+fn calculate_area_buffered(reader: impl BufRead, plan_builder: PlanBuilder) -> io::Result<usize> {
+    let trench = try_fold_lines(reader, Trench::new(), |mut trench, line| {
+        trench.dig(&parse_line(line, plan_builder)?)?;
+        Ok(trench)
+    })?;
+
+    Ok(trench.lagoon_size()?)
+}
+
+// The whole plan, for looking at the trench and not only at the size of the lagoon
+#[derive(Debug, Clone)]
+pub struct DigPlan {
+    lines: Vec<DigPlanLine>,
+}
+
+impl DigPlan {
+    fn parse(dig_plan: &str, plan_builder: PlanBuilder) -> ParseResult<Self> {
+        let lines = input::lines(dig_plan)
+            .map(|line| parse_line(line, plan_builder))
+            .collect::<ParseResult<Vec<_>>>()?;
+
+        let plan = Self { lines };
+        plan.trench()?.lagoon_size()?;
+
+        Ok(plan)
+    }
+
+    pub fn from_directions(dig_plan: &str) -> ParseResult<Self> {
+        Self::parse(dig_plan, plan_part_one)
+    }
+
+    pub fn from_colors(dig_plan: &str) -> ParseResult<Self> {
+        Self::parse(dig_plan, plan_part_two)
+    }
+
+    pub fn lines(&self) -> &[DigPlanLine] {
+        &self.lines
+    }
+
+    // Where each line starts digging, from (0, 0)
+    pub fn vertices(&self) -> Vec<Point> {
+        self.edges().map(|(start, _, _)| start).collect()
+    }
 
-            plan_builder(direction, parts[1].parse().unwrap(), color)
+    fn edges(&self) -> impl Iterator<Item = (Point, Point, &DigPlanLine)> {
+        self.lines.iter().scan(Point::new(0, 0), |position, line| {
+            let start = position.clone();
+            *position = line.direction.go(&start, line.length)?;
+            Some((start, position.clone(), line))
         })
-        .collect();
-
-    let mut position = Point::new(0, 0);
-    let mut points = Vec::new();
-    let mut perimeter = 0;
-
-    for plan in plans {
-        points.push(position.clone());
-        match plan.direction {
-            Direction::Up => position.y -= plan.length as isize,
-            Direction::Down => position.y += plan.length as isize,
-            Direction::Left => position.x -= plan.length as isize,
-            Direction::Right => position.x += plan.length as isize,
+    }
+
+    pub fn perimeter(&self) -> usize {
+        self.lines.iter().map(|line| line.length).sum()
+    }
+
+    // Parsing made sure the trench fits and closes into a lagoon
+    pub fn interior(&self) -> usize {
+        self.trench()
+            .ok()
+            .and_then(|trench| trench.interior())
+            .expect("The trench was checked when parsing")
+    }
+
+    pub fn lagoon_size(&self) -> usize {
+        self.perimeter() + self.interior()
+    }
+
+    fn trench(&self) -> ParseResult<Trench> {
+        let mut trench = Trench::new();
+
+        for line in &self.lines {
+            trench.dig(line)?;
+        }
+
+        Ok(trench)
+    }
+
+    fn bounds(&self) -> (Point, Point) {
+        let vertices = self.vertices();
+        let min = |axis: fn(&Point) -> isize| vertices.iter().map(axis).min().unwrap_or(0);
+        let max = |axis: fn(&Point) -> isize| vertices.iter().map(axis).max().unwrap_or(0);
+
+        (
+            Point::new(min(|p| p.x), min(|p| p.y)),
+            Point::new(max(|p| p.x), max(|p| p.y)),
+        )
+    }
+
+    // The trench as #, what it encloses as o, for lagoons of at most `max_cells` cells
+    pub fn render(&self, max_cells: usize) -> Option<String> {
+        let (min, max) = self.bounds();
+        let size =
+            |min: isize, max: isize| usize::try_from(max.checked_sub(min)?).ok()?.checked_add(1);
+        let (width, height) = (size(min.x, max.x)?, size(min.y, max.y)?);
+
+        if width.checked_mul(height)? > max_cells {
+            return None;
+        }
+
+        let mut trench = HashSet::new();
+        let mut uprights = Vec::new();
+
+        for (start, end, line) in self.edges() {
+            trench.extend((0..=line.length).filter_map(|step| line.direction.go(&start, step)));
+
+            if start.x == end.x && start.y != end.y {
+                uprights.push((start.x, start.y.min(end.y), start.y.max(end.y)));
+            }
+        }
+
+        // Inside when a ray to the right crosses the trench's uprights an odd number of times,
+        // each upright counting from its top row down to just before its bottom row
+        let inside = |x: isize, y: isize| {
+            uprights
+                .iter()
+                .filter(|&&(ux, top, bottom)| ux > x && top <= y && y < bottom)
+                .count()
+                % 2
+                == 1
+        };
+
+        let mut image = String::with_capacity((width + 1) * height);
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                image.push(if trench.contains(&Point::new(x, y)) {
+                    '#'
+                } else if inside(x, y) {
+                    'o'
+                } else {
+                    '.'
+                });
+            }
+            image.push('\n');
         }
-        perimeter += plan.length;
+
+        Some(image)
     }
 
-    let area = Point::shoelace_area(&points) as usize;
+    // Each edge of the trench drawn in its own color
+    pub fn svg(&self) -> String {
+        let (min, max) = self.bounds();
+        let stroke = ((max.x - min.x).max(max.y - min.y) / 200).max(1);
+        let pad = stroke * 2;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+            min.x - pad,
+            min.y - pad,
+            max.x - min.x + 2 * pad,
+            max.y - min.y + 2 * pad
+        );
 
-    // Pick's Theorem
-    // Area = Inside + InEdge/2  - 1
-    // Inside = Area - InEdge/2  + 1
+        for (start, end, line) in self.edges() {
+            let _ = writeln!(
+                svg,
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" \
+                 stroke-width=\"{stroke}\" stroke-linecap=\"square\"/>",
+                start.x, start.y, end.x, end.y, line.color
+            );
+        }
 
-    let inside = area - perimeter / 2 + 1;
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
 
-    perimeter + inside
+// Keeps only running sums, so plans of any length can be dug
+#[derive(Debug)]
+struct Trench {
+    position: Point,
+    // Shoelace sum, accumulated one edge at a time
+    double_area: isize,
+    perimeter: usize,
 }
 
-#[derive(Debug, Clone)]
-enum Direction {
+impl Trench {
+    fn new() -> Self {
+        Self {
+            position: Point::new(0, 0),
+            double_area: 0,
+            perimeter: 0,
+        }
+    }
+
+    // Fails when the trench goes further than the sums can count
+    fn dig(&mut self, plan: &DigPlanLine) -> ParseResult<()> {
+        let too_far = || ParseError::new("The trench goes further than fits");
+        let start = self.position.clone();
+        let end = plan.direction.go(&start, plan.length).ok_or_else(too_far)?;
+
+        self.double_area = start
+            .x
+            .checked_mul(end.y)
+            .zip(end.x.checked_mul(start.y))
+            .and_then(|(a, b)| a.checked_sub(b))
+            .and_then(|double_area| self.double_area.checked_add(double_area))
+            .ok_or_else(too_far)?;
+        self.perimeter = self
+            .perimeter
+            .checked_add(plan.length)
+            .ok_or_else(too_far)?;
+        self.position = end;
+
+        Ok(())
+    }
+
+    // None when the trench runs back over itself, which leaves less area than Pick's Theorem
+    // needs for its edge
+    fn interior(&self) -> Option<usize> {
+        let area = self.double_area.unsigned_abs() / 2;
+
+        // Pick's Theorem
+        // Area = Inside + InEdge/2  - 1
+        // Inside = Area - InEdge/2  + 1
+        // Adding first, as a 1x1 loop has less area than half its perimeter
+
+        (area + 1).checked_sub(self.perimeter / 2)
+    }
+
+    // Only a trench back where it started encloses a lagoon
+    fn lagoon_size(&self) -> ParseResult<usize> {
+        if self.position != Point::new(0, 0) {
+            return Err(ParseError::new(format!(
+                "The trench ends at ({}, {}) instead of where it started",
+                self.position.x, self.position.y
+            )));
+        }
+
+        let interior = self
+            .interior()
+            .ok_or_else(|| ParseError::new("The trench runs back over itself"))?;
+
+        Ok(self.perimeter + interior)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
     Up,
     Down,
     Left,
@@ -71,36 +374,131 @@ enum Direction {
 }
 
 impl Direction {
-    fn parse(char: char) -> Self {
-        match char {
-            'U' => Self::Up,
-            'D' => Self::Down,
-            'R' => Self::Right,
-            'L' => Self::Left,
-            _ => panic!("Unexpected char for diretion: {char}"),
-        }
+    // None when the end doesn't fit
+    fn go(&self, from: &Point, length: usize) -> Option<Point> {
+        let length = isize::try_from(length).ok()?;
+
+        Some(match self {
+            Direction::Up => Point::new(from.x, from.y.checked_sub(length)?),
+            Direction::Down => Point::new(from.x, from.y.checked_add(length)?),
+            Direction::Left => Point::new(from.x.checked_sub(length)?, from.y),
+            Direction::Right => Point::new(from.x.checked_add(length)?, from.y),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    // (#70c710)
+    pub fn parse(color: &str) -> ParseResult<Self> {
+        let hex = color
+            .strip_prefix("(#")
+            .and_then(|color| color.strip_suffix(')'))
+            .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| ParseError::new(format!("Invalid color {color}")))?;
+        let hex = u32::from_str_radix(hex, 16)
+            .map_err(|_| ParseError::new(format!("Invalid color {color}")))?;
+
+        Ok(Self {
+            red: (hex >> 16) as u8,
+            green: (hex >> 8) as u8,
+            blue: hex as u8,
+        })
     }
 
-    fn parse_part_two(char: char) -> Self {
-        match char {
-            '0' => Self::Right,
-            '1' => Self::Down,
-            '2' => Self::Left,
-            '3' => Self::Up,
-            _ => panic!("Invalid direction character"),
-        }
+    fn hex(&self) -> u32 {
+        (self.red as u32) << 16 | (self.green as u32) << 8 | self.blue as u32
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:06x}", self.hex())
     }
 }
 
 #[derive(Debug, Clone)]
-struct DigPlanLine {
-    direction: Direction,
-    length: usize,
+pub struct DigPlanLine {
+    pub direction: Direction,
+    pub length: usize,
+    pub color: Color,
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
+    use crate::strategies::{flood_outside, rectilinear_loop};
+
+    // The loop's steps joined into runs, with the color holding the same run
+    fn plan_str(points: &[(isize, isize)]) -> String {
+        let mut runs: Vec<((isize, isize), usize)> = Vec::new();
+
+        for (i, &(x, y)) in points.iter().enumerate() {
+            let (next_x, next_y) = points[(i + 1) % points.len()];
+            let step = (next_x - x, next_y - y);
+
+            match runs.last_mut() {
+                Some((direction, length)) if *direction == step => *length += 1,
+                _ => runs.push((step, 1)),
+            }
+        }
+
+        runs.iter()
+            .map(|&(step, length)| {
+                let (letter, digit) = match step {
+                    (1, 0) => ('R', 0),
+                    (0, 1) => ('D', 1),
+                    (-1, 0) => ('L', 2),
+                    (0, -1) => ('U', 3),
+                    _ => unreachable!(),
+                };
+                format!("{letter} {length} (#{length:05x}{digit})")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // Every cell of the trench and of what it encloses, counted by flooding around it
+    fn naive_lagoon_size(points: &[(isize, isize)]) -> usize {
+        let trench = points
+            .iter()
+            .map(|&(x, y)| (x as usize + 1, y as usize + 1))
+            .collect::<HashSet<_>>();
+        let width = trench.iter().map(|cell| cell.0).max().unwrap() + 2;
+        let height = trench.iter().map(|cell| cell.1).max().unwrap() + 2;
+
+        let outside = flood_outside(width, height, |x, y| trench.contains(&(x, y)));
+
+        outside
+            .iter()
+            .flatten()
+            .filter(|&&outside| !outside)
+            .count()
+    }
+
+    proptest! {
+        #[test]
+        fn matches_flooding(points in rectilinear_loop(8, 4)) {
+            let dig_plan = plan_str(&points);
+            let size = naive_lagoon_size(&points);
+
+            prop_assert_eq!(size, part_one(&dig_plan));
+            prop_assert_eq!(size, part_two(&dig_plan));
+            prop_assert_eq!(size, part_one_buffered(dig_plan.as_bytes()).unwrap());
+            prop_assert_eq!(size, part_two_buffered(dig_plan.as_bytes()).unwrap());
+
+            let rendered = DigPlan::from_directions(&dig_plan).unwrap().render(usize::MAX).unwrap();
+            prop_assert_eq!(size, rendered.chars().filter(|&c| c == '#' || c == 'o').count());
+        }
+    }
 
     #[test]
     fn example() {
@@ -122,6 +520,103 @@ mod tests {
 
         assert_eq!(62, part_one(dig_plan));
         assert_eq!(952408144115, part_two(dig_plan));
+        assert_eq!(62, part_one_buffered(dig_plan.as_bytes()).unwrap());
+        assert_eq!(
+            952408144115,
+            part_two_buffered(dig_plan.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn drawing() {
+        let dig_plan = "\
+        R 6 (#70c710)
+        D 5 (#0dc571)
+        L 2 (#5713f0)
+        D 2 (#d2c081)
+        R 2 (#59c680)
+        D 2 (#411b91)
+        L 5 (#8ceee2)
+        U 2 (#caa173)
+        L 1 (#1b58a2)
+        U 2 (#caa171)
+        R 2 (#7807d2)
+        U 3 (#a77fa3)
+        L 2 (#015232)
+        U 2 (#7a21e3)";
+
+        let plan = DigPlan::from_directions(dig_plan).unwrap();
+        assert_eq!(38, plan.perimeter());
+        assert_eq!(24, plan.interior());
+        assert_eq!(&[Point::new(0, 0), Point::new(6, 0)], &plan.vertices()[..2]);
+
+        let lagoon = "\
+#######
+#ooooo#
+###ooo#
+..#ooo#
+..#ooo#
+###o###
+#ooo#..
+##oo###
+.#oooo#
+.######
+";
+        assert_eq!(None, plan.render(10));
+        assert_eq!(lagoon, plan.render(100).unwrap());
+
+        let svg = plan.svg();
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-2 -2 10 13\">")
+        );
+        assert!(svg.contains("<line x1=\"0\" y1=\"0\" x2=\"6\" y2=\"0\" stroke=\"#70c710\""));
+        assert_eq!(14, svg.matches("<line").count());
+
+        let plan = DigPlan::from_colors(dig_plan).unwrap();
+        assert_eq!(Direction::Right, plan.lines()[0].direction);
+        assert_eq!(461937, plan.lines()[0].length);
+        assert_eq!("#70c710", plan.lines()[0].color.to_string());
+    }
+
+    #[test]
+    fn bad_colors() {
+        assert!(DigPlan::from_directions("R 6 (#70c71)").is_err());
+        assert!(DigPlan::from_directions("R 6 (#70g710)").is_err());
+        assert!(DigPlan::from_directions("R 6 #70c710").is_err());
+        assert!(DigPlan::from_directions("R 6 (#+0c710)").is_err());
+        assert!(DigPlan::from_colors("R 6 (#70c714)").is_err());
+        assert!(DigPlan::from_directions(
+            "R 1 (#70c714)\nD 1 (#000011)\nL 1 (#000012)\nU 1 (#000013)"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn unclosed_plans() {
+        assert!(DigPlan::from_directions("R 6 (#70c710)").is_err());
+        assert!(DigPlan::from_colors("R 6 (#70c710)").is_err());
+        assert!(Day18::parse("R 6 (#70c710)").is_err());
+        assert!(part_one_buffered("R 6 (#70c710)\nD 5 (#0dc571)".as_bytes()).is_err());
+
+        // Closed, but with nothing inside for the edge to go round
+        let there_and_back = "R 2 (#000020)\nL 2 (#000022)";
+        assert!(DigPlan::from_directions(there_and_back).is_err());
+        assert!(part_two_buffered(there_and_back.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn too_far() {
+        let square = |side: usize| {
+            format!(
+                "R {side} (#000000)\nD {side} (#000000)\nL {side} (#000000)\nU {side} (#000000)"
+            )
+        };
+
+        let side = 1 << 30;
+        assert_eq!((side + 1) * (side + 1), part_one(&square(side)));
+        assert!(DigPlan::from_directions(&square(1 << 40)).is_err());
+        assert!(DigPlan::from_directions(&square(usize::MAX)).is_err());
+        assert!(part_one_buffered(square(1 << 40).as_bytes()).is_err());
     }
 
     #[test]
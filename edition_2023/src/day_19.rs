@@ -1,15 +1,49 @@
-use std::{collections::HashMap, fmt::Debug, ops::Range};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    ops::Range,
+};
 
 use regex::{Match, Regex};
 
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseError, ParseResult, Required},
+    solution::Solution,
+};
+
 pub fn part_one(input: &str) -> isize {
-    let program = Program::parse_program(input);
+    sum_accepted(&Program::parse_program(input).unwrap())
+}
+
+pub fn part_two(input: &str) -> usize {
+    count_accepted_combinations(&Program::parse_program(input).unwrap())
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    type Parsed<'a> = Program;
+
+    fn parse(input: &str) -> ParseResult<Self::Parsed<'_>> {
+        Program::parse_program(input)
+    }
 
+    fn part_one(program: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        sum_accepted(program).to_string()
+    }
+
+    fn part_two(program: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(count_accepted_combinations(program).to_string())
+    }
+}
+
+fn sum_accepted(program: &Program) -> isize {
     program.run().iter().map(Part::sum).sum()
 }
 
-pub fn part_two(input: &str) -> usize {
-    let program = Program::parse_program(input);
+fn count_accepted_combinations(program: &Program) -> usize {
     let possible_checks = program.possible_checks();
 
     possible_checks
@@ -19,30 +53,76 @@ pub fn part_two(input: &str) -> usize {
 }
 
 #[derive(Debug)]
-struct Program {
+pub struct Program {
     symbol_table: HashMap<String, Workflow>,
     parts: Vec<Part>,
 }
 
 impl Program {
-    fn parse_program(string: &str) -> Self {
+    fn parse_program(string: &str) -> ParseResult<Self> {
         let mut symbol_table = HashMap::new();
         let mut parts = Vec::new();
 
-        for line in string.lines() {
-            let line = line.trim();
-
+        for line in input::lines(string).filter(|line| !line.is_empty()) {
             if let Some(workflow) = Workflow::parse(line) {
+                let workflow = workflow?;
                 symbol_table.insert(workflow.name.clone(), workflow);
             } else if let Some(part) = Part::parse(line) {
-                parts.push(part);
+                parts.push(part?);
+            } else {
+                return Err(ParseError::new(format!(
+                    "Neither a workflow nor a part: {line}"
+                )));
             }
         }
 
-        Self {
+        let program = Self {
             symbol_table,
             parts,
+        };
+        program.check_flow()?;
+
+        Ok(program)
+    }
+
+    // Running follows every label from "in", so they must all exist and never loop back
+    fn check_flow(&self) -> ParseResult<()> {
+        self.symbol_table
+            .get("in")
+            .required("the \"in\" workflow")?;
+
+        let mut done = HashSet::new();
+        let mut path = Vec::new();
+        self.check_from("in", &mut path, &mut done)
+    }
+
+    fn check_from<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> ParseResult<()> {
+        if path.contains(&name) {
+            return Err(ParseError::new(format!(
+                "Workflow {name} loops back to itself"
+            )));
+        }
+        if !done.insert(name) {
+            return Ok(());
         }
+
+        let workflow = self
+            .symbol_table
+            .get(name)
+            .ok_or_else(|| ParseError::new(format!("Workflow {name} not found")))?;
+
+        path.push(name);
+        for label in workflow.instructions.iter().filter_map(Instruction::label) {
+            self.check_from(label, path, done)?;
+        }
+        path.pop();
+
+        Ok(())
     }
 
     fn run(&self) -> Vec<Part> {
@@ -109,12 +189,13 @@ struct Part {
 }
 
 lazy_static! {
-    static ref PART_REGEX: Regex = Regex::new(r"\{x=(\d+),m=(\d+),a=(\d+),s=(\d+)\}").unwrap();
+    static ref PART_REGEX: Regex = Regex::new(r"^\{x=(\d+),m=(\d+),a=(\d+),s=(\d+)\}$").unwrap();
 }
 
 impl Part {
-    fn parse(string: &str) -> Option<Self> {
-        PART_REGEX.captures(string).and_then(|caps| {
+    // None when the line isn't shaped like a part
+    fn parse(string: &str) -> Option<ParseResult<Self>> {
+        PART_REGEX.captures(string).map(|caps| {
             let mut props = caps.iter().skip(1).flatten();
 
             let x = Self::parse_prop(&mut props)?;
@@ -122,12 +203,12 @@ impl Part {
             let a = Self::parse_prop(&mut props)?;
             let s = Self::parse_prop(&mut props)?;
 
-            Some(Self { x, m, a, s })
+            Ok(Self { x, m, a, s })
         })
     }
 
-    fn parse_prop<'a>(props: &mut impl Iterator<Item = Match<'a>>) -> Option<isize> {
-        props.next()?.as_str().parse::<isize>().ok()
+    fn parse_prop<'a>(props: &mut impl Iterator<Item = Match<'a>>) -> ParseResult<isize> {
+        rating(props.next().required("a rating")?.as_str())
     }
 
     fn get(&self, prop: &PartProp) -> isize {
@@ -203,23 +284,30 @@ struct Workflow {
 }
 
 lazy_static! {
-    static ref WORKFLOW_REGEX: Regex = Regex::new(r"(\w+)\{([^}]+)\}").unwrap();
-    static ref GREATER_LESS_REGEX: Regex = Regex::new(r"(\w+)([><])(\d+):(.+)").unwrap();
+    static ref WORKFLOW_REGEX: Regex = Regex::new(r"^(\w+)\{([^}]+)\}$").unwrap();
+    static ref GREATER_LESS_REGEX: Regex = Regex::new(r"^(\w+)([><])(\d+):(.+)$").unwrap();
+    static ref LABEL_REGEX: Regex = Regex::new(r"^\w+$").unwrap();
+}
+
+// Ratings go from 1 to 4000, anything that fits in 32 bits can't overflow the ranges
+fn rating(text: &str) -> ParseResult<isize> {
+    Ok(number::<u32>(text)? as isize)
 }
 
 impl Workflow {
-    fn parse(string: &str) -> Option<Self> {
-        if let Some(caps) = WORKFLOW_REGEX.captures(string) {
-            let name = caps.get(1)?.as_str();
-            let instruction_text = caps.get(2)?.as_str().split(',');
-
-            Some(Self {
-                name: String::from(name),
-                instructions: instruction_text.flat_map(Instruction::parse).collect(),
-            })
-        } else {
-            None
-        }
+    // None when the line isn't shaped like a workflow
+    fn parse(string: &str) -> Option<ParseResult<Self>> {
+        let caps = WORKFLOW_REGEX.captures(string)?;
+        let name = &caps[1];
+        let instructions = caps[2]
+            .split(',')
+            .map(Instruction::parse)
+            .collect::<ParseResult<Vec<_>>>();
+
+        Some(instructions.map(|instructions| Self {
+            name: String::from(name),
+            instructions,
+        }))
     }
 
     fn possible_checks(
@@ -276,27 +364,26 @@ impl Instruction {
         }
     }
 
-    fn parse(text: &str) -> Option<Instruction> {
+    fn parse(text: &str) -> ParseResult<Instruction> {
         if text == "A" {
-            return Some(Instruction::Accept);
+            return Ok(Instruction::Accept);
         }
         if text == "R" {
-            return Some(Instruction::Reject);
+            return Ok(Instruction::Reject);
         }
 
         if let Some(caps) = GREATER_LESS_REGEX.captures(text) {
-            let prop = PartProp::parse(caps.get(1)?.as_str())?;
-            let simbol = caps.get(2)?.as_str();
-            let to_compare = caps.get(3)?.as_str().parse::<isize>().ok()?;
-            let on_accept = Box::new(Instruction::parse(caps.get(4)?.as_str())?);
+            let prop = PartProp::parse(&caps[1])
+                .ok_or_else(|| ParseError::new(format!("Unknown rating {}", &caps[1])))?;
+            let to_compare = rating(&caps[3])?;
+            let on_accept = Box::new(Instruction::parse(&caps[4])?);
 
-            let kind = match simbol {
+            let kind = match &caps[2] {
                 ">" => ComparisonType::Greater,
-                "<" => ComparisonType::Less,
-                _ => panic!("Invalid comparison sign {simbol}"),
+                _ => ComparisonType::Less,
             };
 
-            return Some(Instruction::Comparison {
+            return Ok(Instruction::Comparison {
                 prop,
                 kind,
                 to_compare,
@@ -304,11 +391,24 @@ impl Instruction {
             });
         }
 
-        Some(Instruction::Goto {
+        if !LABEL_REGEX.is_match(text) {
+            return Err(ParseError::new(format!("Invalid instruction {text}")));
+        }
+
+        Ok(Instruction::Goto {
             label: String::from(text),
         })
     }
 
+    // The workflow this instruction may go to
+    fn label(&self) -> Option<&str> {
+        match self {
+            Instruction::Goto { label } => Some(label),
+            Instruction::Comparison { on_accept, .. } => on_accept.label(),
+            _ => None,
+        }
+    }
+
     fn possible_checks(
         &self,
         symbol_table: &HashMap<String, Workflow>,
@@ -331,14 +431,15 @@ impl Instruction {
                 let mut_true = true_range.get_mut(prop);
                 let mut_false = range.get_mut(prop);
 
+                // Only ever narrow, earlier checks may have already cut past the value
                 match kind {
                     ComparisonType::Greater => {
-                        mut_true.start = to_compare + 1;
-                        mut_false.end = to_compare + 1;
+                        mut_true.start = mut_true.start.max(to_compare + 1);
+                        mut_false.end = mut_false.end.min(to_compare + 1);
                     }
                     ComparisonType::Less => {
-                        mut_true.end = *to_compare;
-                        mut_false.start = *to_compare;
+                        mut_true.end = mut_true.end.min(*to_compare);
+                        mut_false.start = mut_false.start.max(*to_compare);
                     }
                 };
 
@@ -358,11 +459,176 @@ impl Instruction {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
+    const PROPS: [char; 4] = ['x', 'm', 'a', 's'];
+
+    #[derive(Debug, Clone, Copy)]
+    enum Target {
+        Accept,
+        Reject,
+        Workflow(usize),
+    }
+
+    // (prop, is greater, value, target) rules and the fallback target
+    type Rules = (Vec<(usize, bool, isize, Target)>, Target);
+
+    // Workflows only go to later ones, so every part ends up accepted or rejected.
+    // Few distinct values make the same prop get compared again further down
+    fn workflows() -> impl Strategy<Value = Vec<Rules>> {
+        let value = prop::sample::select(vec![1, 1000, 1500, 2000, 2500, 3000, 4000]);
+        let rule = (
+            0..4_usize,
+            any::<bool>(),
+            value,
+            any::<prop::sample::Index>(),
+        );
+        let workflow = (
+            prop::collection::vec(rule, 0..=3),
+            any::<prop::sample::Index>(),
+        );
+
+        prop::collection::vec(workflow, 1..=5).prop_map(|workflows| {
+            let n = workflows.len();
+            let target = |i: usize, index: prop::sample::Index| match index.index(n + 1 - i) {
+                0 => Target::Accept,
+                1 => Target::Reject,
+                next => Target::Workflow(i + next - 1),
+            };
+
+            workflows
+                .into_iter()
+                .enumerate()
+                .map(|(i, (rules, fallback))| {
+                    let rules = rules
+                        .into_iter()
+                        .map(|(prop, greater, value, index)| {
+                            (prop, greater, value, target(i, index))
+                        })
+                        .collect();
+                    (rules, target(i, fallback))
+                })
+                .collect()
+        })
+    }
+
+    fn name(target: Target) -> String {
+        match target {
+            Target::Accept => String::from("A"),
+            Target::Reject => String::from("R"),
+            Target::Workflow(0) => String::from("in"),
+            Target::Workflow(i) => format!("w{i}"),
+        }
+    }
+
+    fn system_str(workflows: &[Rules], parts: &[[isize; 4]]) -> String {
+        let workflows = workflows.iter().enumerate().map(|(i, (rules, fallback))| {
+            let rules = rules
+                .iter()
+                .map(|&(prop, greater, value, target)| {
+                    let sign = if greater { '>' } else { '<' };
+                    format!("{}{sign}{value}:{}", PROPS[prop], name(target))
+                })
+                .chain([name(*fallback)])
+                .collect::<Vec<_>>();
+            format!("{}{{{}}}", name(Target::Workflow(i)), rules.join(","))
+        });
+        let parts = parts
+            .iter()
+            .map(|[x, m, a, s]| format!("{{x={x},m={m},a={a},s={s}}}"));
+
+        workflows
+            .chain([String::new()])
+            .chain(parts)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn is_accepted(workflows: &[Rules], part: &[isize; 4]) -> bool {
+        let mut current = 0;
+
+        loop {
+            let (rules, fallback) = &workflows[current];
+            let target = rules
+                .iter()
+                .find(|&&(prop, greater, value, _)| {
+                    if greater {
+                        part[prop] > value
+                    } else {
+                        part[prop] < value
+                    }
+                })
+                .map_or(*fallback, |rule| rule.3);
+
+            match target {
+                Target::Accept => return true,
+                Target::Reject => return false,
+                Target::Workflow(next) => current = next,
+            }
+        }
+    }
+
+    // Splits each prop at the values compared against it, then tries one part per cell of the
+    // split, as every part in a cell takes the same path
+    fn naive_combinations(workflows: &[Rules]) -> usize {
+        let bounds = (0..PROPS.len())
+            .map(|prop| {
+                let mut bounds = vec![1, 4001];
+                for (rules, _) in workflows {
+                    for &(_, greater, value, _) in rules.iter().filter(|rule| rule.0 == prop) {
+                        bounds.push(if greater { value + 1 } else { value });
+                    }
+                }
+                bounds.sort_unstable();
+                bounds.dedup();
+                bounds
+            })
+            .collect::<Vec<_>>();
+
+        let mut cells = vec![(vec![], 1)];
+        for bounds in &bounds {
+            cells = cells
+                .into_iter()
+                .flat_map(|(part, size)| {
+                    bounds.windows(2).map(move |window| {
+                        let mut part: Vec<isize> = part.clone();
+                        part.push(window[0]);
+                        (part, size * (window[1] - window[0]) as usize)
+                    })
+                })
+                .collect();
+        }
+
+        cells
+            .iter()
+            .filter(|(part, _)| is_accepted(workflows, &[part[0], part[1], part[2], part[3]]))
+            .map(|(_, size)| size)
+            .sum()
+    }
+
+    proptest! {
+        #[test]
+        fn matches_trying_every_cell(
+            workflows in workflows(),
+            parts in prop::collection::vec(prop::array::uniform4(1..=4000_isize), 0..5),
+        ) {
+            let system = system_str(&workflows, &parts);
+            let accepted = parts
+                .iter()
+                .filter(|part| is_accepted(&workflows, part))
+                .map(|part| part.iter().sum::<isize>())
+                .sum::<isize>();
+
+            prop_assert_eq!(accepted, part_one(&system));
+            prop_assert_eq!(naive_combinations(&workflows), part_two(&system));
+        }
+    }
+
     #[test]
     fn example() {
-        let input = "\\
+        let input = "\
         px{a<2006:qkq,m>2090:A,rfg}
         pv{a>1716:R,A}
         lnx{m>1548:A,A}
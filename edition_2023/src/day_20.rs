@@ -1,38 +1,102 @@
 use std::{
     cell::RefCell,
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     vec,
 };
 
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{ParseError, ParseResult, Required},
+    solution::Solution,
+};
+
 pub fn part_one(input: &str) -> usize {
+    count_pulses(&parse_modules(input).unwrap())
+}
+
+pub fn part_two(input: &str) -> Option<usize> {
+    presses_to_turn_on(&parse_modules(input).unwrap())
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    type Parsed<'a> = Modules;
+
+    fn parse(input: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse_modules(input)
+    }
+
+    // Pressing the button changes the modules, so every run starts from a copy
+    fn part_one(modules: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        count_pulses(&modules.clone()).to_string()
+    }
+
+    fn part_two(modules: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(
+            presses_to_turn_on(&modules.clone())
+                .map_or_else(|| String::from("never"), |presses| presses.to_string()),
+        )
+    }
+}
+
+fn count_pulses(modules: &Modules) -> usize {
     let mut pulse_count = (0, 0);
     let mut cycles = HashMap::new();
 
-    let modules = parse_modules(input);
-
     for _ in 0..1000 {
-        press_button(&modules, &mut pulse_count, &mut cycles, 0, "");
+        press_button(modules, &mut pulse_count, &mut cycles, 0, "");
     }
 
     pulse_count.0 * pulse_count.1
 }
 
-pub fn part_two(input: &str) -> usize {
+// rx gets a low pulse once the conjunction feeding it has had a high pulse from each of its
+// inputs in the same press, which happens when the cycles of all the inputs line up. None when
+// no conjunction feeds rx, or when the modules come back to how they were before every input
+// has sent its high pulse
+fn presses_to_turn_on(modules: &Modules) -> Option<usize> {
+    let mut feeders = modules
+        .values()
+        .filter(|module| module.borrow().targets.iter().any(|target| target == "rx"));
+    let (Some(feeder), None) = (feeders.next(), feeders.next()) else {
+        return None;
+    };
+
+    let (feeder, inputs) = match &feeder.borrow().kind {
+        ModuleType::Conjunction { memory } => (feeder.borrow().name.clone(), memory.len()),
+        _ => return None,
+    };
+
     let mut cycles = HashMap::new();
+    let mut seen = HashSet::new();
     let mut presses = 0;
 
-    let modules = parse_modules(input);
+    while cycles.len() < inputs {
+        if !seen.insert(state(modules)) {
+            return None;
+        }
 
-    loop {
         presses += 1;
-        press_button(&modules, &mut (0, 0), &mut cycles, presses, "sq");
-
-        if cycles.len() == 4 {
-            break;
-        }
+        press_button(modules, &mut (0, 0), &mut cycles, presses, &feeder);
     }
 
-    cycles.values().copied().reduce(num::integer::lcm).unwrap()
+    cycles.values().copied().reduce(num::integer::lcm)
+}
+
+// What the flip-flops and conjunctions remember, always listed in the same order
+fn state(modules: &Modules) -> Vec<bool> {
+    modules
+        .values()
+        .flat_map(|module| match &module.borrow().kind {
+            ModuleType::FlipFlop { on } => vec![*on],
+            ModuleType::Conjunction { memory } => {
+                memory.values().map(|&pulse| pulse == Pulse::High).collect()
+            }
+            ModuleType::Broadcast => vec![],
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -41,47 +105,47 @@ enum Pulse {
     Low,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 enum ModuleType {
     FlipFlop { on: bool },
     Conjunction { memory: HashMap<String, Pulse> },
     Broadcast,
 }
 
-#[derive(Debug)]
-struct Module {
+#[derive(Debug, Clone)]
+pub struct Module {
     name: String,
     kind: ModuleType,
     targets: Vec<String>,
 }
 
 impl Module {
-    fn parse(line: &str) -> Self {
-        let parts = line.trim().split(" -> ").collect::<Vec<_>>();
-        let name = String::from(parts[0]);
-        let targets = parts[1].split(", ").map(String::from).collect::<Vec<_>>();
-
-        if name == "broadcaster" {
-            Module {
+    fn parse(line: &str) -> ParseResult<Self> {
+        let (name, targets) = line
+            .split_once(" -> ")
+            .required("the ' -> ' after the module")?;
+        let targets = targets.split(", ").map(String::from).collect::<Vec<_>>();
+
+        let (name, kind) = if name == "broadcaster" {
+            (name, ModuleType::Broadcast)
+        } else if let Some(name) = name.strip_prefix('%') {
+            (name, ModuleType::FlipFlop { on: false })
+        } else if let Some(name) = name.strip_prefix('&') {
+            (
                 name,
-                kind: ModuleType::Broadcast,
-                targets,
-            }
-        } else {
-            let kind = if name.starts_with('%') {
-                ModuleType::FlipFlop { on: false }
-            } else {
                 ModuleType::Conjunction {
                     memory: HashMap::new(),
-                }
-            };
-
-            Module {
-                name: String::from(&name[1..]),
-                kind,
-                targets,
-            }
-        }
+                },
+            )
+        } else {
+            return Err(ParseError::new(format!("Unknown module type for {name}")));
+        };
+
+        Ok(Module {
+            name: String::from(name),
+            kind,
+            targets,
+        })
     }
 
     fn process_pulse(&mut self, sender: &str, pulse: &Pulse) -> Option<Pulse> {
@@ -110,13 +174,16 @@ impl Module {
     }
 }
 
-fn parse_modules(input: &str) -> HashMap<String, RefCell<Module>> {
-    let modules: HashMap<String, RefCell<Module>> = input
-        .lines()
-        .map(Module::parse)
-        .map(|m| (m.name.clone(), m))
-        .map(|(key, val)| (key, RefCell::new(val)))
-        .collect();
+type Modules = HashMap<String, RefCell<Module>>;
+
+fn parse_modules(configuration: &str) -> ParseResult<Modules> {
+    let modules: Modules = input::lines(configuration)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let module = Module::parse(line)?;
+            Ok((module.name.clone(), RefCell::new(module)))
+        })
+        .collect::<ParseResult<_>>()?;
 
     for (name, module) in modules.iter() {
         let module = &mut module.borrow_mut();
@@ -132,11 +199,53 @@ fn parse_modules(input: &str) -> HashMap<String, RefCell<Module>> {
         }
     }
 
+    check_loops(&modules)?;
+    Ok(modules)
+}
+
+// Conjunctions and the broadcaster answer every pulse, so a pulse going round a loop of only
+// them never stops. Flip-flops ignore high pulses, so each loop needs one
+fn check_loops(modules: &Modules) -> ParseResult<()> {
+    fn visit<'a>(
+        name: &'a str,
+        modules: &'a Modules,
+        on_path: &mut HashSet<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> ParseResult<()> {
+        let Some(module) = modules.get(name) else {
+            return Ok(());
+        };
+        let module = module.borrow();
+
+        if done.contains(name) || matches!(module.kind, ModuleType::FlipFlop { .. }) {
+            return Ok(());
+        }
+        if !on_path.insert(name) {
+            return Err(ParseError::new(format!(
+                "Pulses would go round {name} forever, with no flip-flop on the way"
+            )));
+        }
+
+        for target in &module.targets {
+            if let Some((target, _)) = modules.get_key_value(target.as_str()) {
+                visit(target, modules, on_path, done)?;
+            }
+        }
+
+        on_path.remove(name);
+        done.insert(name);
+        Ok(())
+    }
+
+    let (mut on_path, mut done) = (HashSet::new(), HashSet::new());
+
     modules
+        .keys()
+        .try_for_each(|name| visit(name, modules, &mut on_path, &mut done))
 }
 
 fn press_button(
-    modules: &HashMap<String, RefCell<Module>>,
+    modules: &Modules,
     pulse_count: &mut (usize, usize),
     cycles: &mut HashMap<String, usize>,
     presses: usize,
@@ -176,7 +285,98 @@ fn press_button(
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
+    use proptest::prelude::*;
+
     use super::*;
+    use crate::generate::counter_network;
+
+    // Four binary counters like in the real input, each counting presses up to an odd period
+    fn counters() -> impl Strategy<Value = (String, Vec<usize>)> {
+        prop::collection::vec((2..=4_usize, any::<prop::sample::Index>()), 4).prop_map(|counters| {
+            let counters = counters
+                .into_iter()
+                .map(|(bits, index)| {
+                    (
+                        bits,
+                        (1 << (bits - 1)) + 1 + 2 * index.index(1 << (bits - 2)),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let periods = counters.iter().map(|&(_, period)| period).collect();
+
+            (counter_network(&counters), periods)
+        })
+    }
+
+    // Presses the button until rx gets a low pulse, following every pulse
+    fn naive_presses(configuration: &str) -> usize {
+        let modules = configuration
+            .lines()
+            .map(|line| {
+                let (name, targets) = line.split_once(" -> ").unwrap();
+                let targets = targets.split(", ").collect::<Vec<_>>();
+                match name.strip_prefix(['%', '&']) {
+                    Some(stripped) => (stripped, (name.starts_with('%'), targets)),
+                    None => (name, (false, targets)),
+                }
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut on = HashSet::new();
+        let mut memory: HashMap<&str, HashMap<&str, bool>> = HashMap::new();
+        for (&name, (_, targets)) in &modules {
+            for &target in targets {
+                memory.entry(target).or_default().insert(name, false);
+            }
+        }
+
+        for presses in 1.. {
+            let mut queue = VecDeque::from([("button", "broadcaster", false)]);
+
+            while let Some((sender, name, high)) = queue.pop_front() {
+                if name == "rx" && !high {
+                    return presses;
+                }
+                let Some((is_flip_flop, targets)) = modules.get(name) else {
+                    continue;
+                };
+
+                let output = if name == "broadcaster" {
+                    high
+                } else if *is_flip_flop {
+                    if high {
+                        continue;
+                    }
+                    if !on.remove(name) {
+                        on.insert(name);
+                    }
+                    on.contains(name)
+                } else {
+                    let inputs = memory.get_mut(name).unwrap();
+                    inputs.insert(sender, high);
+                    !inputs.values().all(|&high| high)
+                };
+
+                queue.extend(targets.iter().map(|&target| (name, target, output)));
+            }
+        }
+
+        unreachable!()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn matches_pressing_until_rx_is_on((configuration, periods) in counters()) {
+            let presses = naive_presses(&configuration);
+
+            prop_assert_eq!(periods.into_iter().reduce(num::integer::lcm).unwrap(), presses);
+            prop_assert_eq!(Some(presses), part_two(&configuration));
+        }
+    }
 
     #[test]
     fn example() {
@@ -199,11 +399,35 @@ mod test {
         assert_eq!(11_687_500, part_one(input));
     }
 
+    #[test]
+    fn without_a_way_to_rx() {
+        assert_eq!(None, part_two("broadcaster -> a\n%a -> inv, con"));
+        assert_eq!(None, part_two("broadcaster -> a\n%a -> rx"));
+        // The conjunction's second input never gets a pulse
+        assert_eq!(
+            None,
+            part_two("broadcaster -> a\n%a -> c\n%b -> c\n&c -> rx")
+        );
+
+        let modules = Day20::parse("broadcaster -> a\n%a -> rx").unwrap();
+        assert_eq!(
+            Some(String::from("never")),
+            Day20::part_two(&modules, &mut Explain::off())
+        );
+    }
+
+    #[test]
+    fn endless_loops() {
+        assert!(Day20::parse("broadcaster -> broadcaster").is_err());
+        assert!(Day20::parse("broadcaster -> a\n&a -> b\n&b -> a").is_err());
+        assert!(Day20::parse("broadcaster -> a\n&a -> b\n%b -> a").is_ok());
+    }
+
     #[test]
     fn real() {
         let input = include_str!("../res/day_20.txt");
 
         assert_eq!(867118762, part_one(input));
-        assert_eq!(217317393039529, part_two(input));
+        assert_eq!(Some(217317393039529), part_two(input));
     }
 }
@@ -1,15 +1,34 @@
 use core::str;
 use std::{collections::HashSet, vec};
 
-use crate::common::Point;
+use crate::common::{
+    explain::Explain,
+    parse::{self, ParseError, ParseResult},
+    solution::Solution,
+    Point,
+};
 
 pub fn part_one(map: &str) -> usize {
-    let garden = Garden::parse(map);
+    let garden = Garden::parse(map).unwrap();
     let possible = garden.find_possible_positions(64);
 
     possible.len()
 }
 
+pub struct Day21;
+
+impl Solution for Day21 {
+    type Parsed<'a> = Garden;
+
+    fn parse(map: &str) -> ParseResult<Self::Parsed<'_>> {
+        Garden::parse(map)
+    }
+
+    fn part_one(garden: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        garden.find_possible_positions(64).len().to_string()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum GardenTileType {
     Starting,
@@ -18,12 +37,12 @@ enum GardenTileType {
 }
 
 impl GardenTileType {
-    fn parse(tile: char) -> Self {
+    fn parse(tile: char) -> ParseResult<Self> {
         match tile {
-            '#' => Self::Rock,
-            'S' => Self::Starting,
-            '.' => Self::Plot,
-            _ => panic!("unknow tile char {tile}"),
+            '#' => Ok(Self::Rock),
+            'S' => Ok(Self::Starting),
+            '.' => Ok(Self::Plot),
+            _ => Err(ParseError::new(format!("Unknown tile char {tile}"))),
         }
     }
 }
@@ -35,28 +54,40 @@ struct GardenTile {
 }
 
 #[derive(Debug)]
-struct Garden {
+pub struct Garden {
     tiles: Vec<Vec<GardenTile>>,
 }
 
 impl Garden {
-    fn parse(map: &str) -> Self {
-        Self {
-            tiles: map
-                .lines()
+    fn parse(map: &str) -> ParseResult<Self> {
+        let kinds = parse::grid(map, GardenTileType::parse)?;
+
+        let starts = kinds
+            .iter()
+            .flatten()
+            .filter(|kind| **kind == GardenTileType::Starting)
+            .count();
+        if starts != 1 {
+            return Err(ParseError::new(format!(
+                "Expected one starting tile, found {starts}"
+            )));
+        }
+
+        Ok(Self {
+            tiles: kinds
+                .into_iter()
                 .enumerate()
                 .map(|(y, line)| {
-                    line.trim()
-                        .chars()
+                    line.into_iter()
                         .enumerate()
-                        .map(|(x, tile)| GardenTile {
-                            kind: GardenTileType::parse(tile),
+                        .map(|(x, kind)| GardenTile {
+                            kind,
                             position: Point::new_usize(x, y),
                         })
                         .collect()
                 })
                 .collect(),
-        }
+        })
     }
 
     fn find_possible_positions(&self, steps: usize) -> HashSet<GardenTile> {
@@ -108,28 +139,6 @@ impl Garden {
 
         possible
     }
-
-    #[allow(dead_code)]
-    fn print(&self, possible: &HashSet<GardenTile>) {
-        for line in &self.tiles {
-            for tile in line {
-                if possible.contains(tile) {
-                    print!("O");
-                } else {
-                    print!(
-                        "{}",
-                        match tile.kind {
-                            GardenTileType::Starting => 'S',
-                            GardenTileType::Rock => '#',
-                            GardenTileType::Plot => '.',
-                        }
-                    );
-                }
-            }
-
-            println!();
-        }
-    }
 }
 
 #[cfg(test)]
@@ -151,10 +160,9 @@ mod test {
         .##..##.##.
         ...........";
 
-        let garden = Garden::parse(input);
+        let garden = Garden::parse(input).unwrap();
         let possible = garden.find_possible_positions(6);
 
-        garden.print(&possible);
         assert_eq!(16, possible.len());
     }
 
@@ -0,0 +1,478 @@
+// Random but valid inputs of any size, to stress and benchmark the days beyond the stored inputs.
+// The same seed always gives the same input
+
+use std::collections::{HashMap, HashSet};
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generated {
+    pub input: String,
+    // The answers known from how the input was built, as the solutions return them
+    pub answers: [Option<String>; 2],
+}
+
+impl Generated {
+    fn new(input: String) -> Self {
+        Self {
+            input,
+            answers: [None, None],
+        }
+    }
+}
+
+// The days with a generator, for the runner
+pub const DAYS: [u8; 6] = [5, 10, 12, 17, 19, 20];
+
+// What the size is depends on the day, see each generator
+pub fn generate(day: u8, seed: u64, size: usize) -> Option<Generated> {
+    let mut generator = Generator::new(seed);
+
+    Some(match day {
+        5 => generator.almanac(size),
+        10 => generator.pipe_maze(size),
+        12 => generator.spring_records(size),
+        17 => generator.heat_map(size, size),
+        19 => generator.workflows(size, size),
+        20 => generator.module_network(size),
+        _ => return None,
+    })
+}
+
+pub struct Generator {
+    rng: ChaCha8Rng,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    // Day 05, seven maps of `ranges` lines each. Each map shuffles consecutive segments of a
+    // window around, so neither the sources nor the destinations overlap
+    pub fn almanac(&mut self, ranges: usize) -> Generated {
+        const MAPS: [&str; 8] = [
+            "seed",
+            "soil",
+            "fertilizer",
+            "water",
+            "light",
+            "temperature",
+            "humidity",
+            "location",
+        ];
+        let ranges = ranges.max(1);
+        // Numbers grow with the size, like the ranges they are spread over
+        let window = 1000 * ranges;
+
+        let seeds = (0..10)
+            .map(|_| {
+                let start = self.rng.gen_range(0..2 * window);
+                format!("{start} {}", self.rng.gen_range(1..=1000))
+            })
+            .collect::<Vec<_>>();
+        let mut almanac = format!("seeds: {}\n", seeds.join(" "));
+
+        for names in MAPS.windows(2) {
+            let base = self.rng.gen_range(0..window);
+            let lengths = (0..ranges)
+                .map(|_| self.rng.gen_range(1..=1000))
+                .collect::<Vec<_>>();
+            let source_starts = lengths
+                .iter()
+                .scan(base, |start, length| {
+                    *start += length;
+                    Some(*start - length)
+                })
+                .collect::<Vec<_>>();
+
+            let mut order = (0..ranges).collect::<Vec<_>>();
+            order.shuffle(&mut self.rng);
+
+            almanac += &format!("\n{}-to-{} map:\n", names[0], names[1]);
+            let mut destination = base;
+            for segment in order {
+                almanac += &format!(
+                    "{destination} {} {}\n",
+                    source_starts[segment], lengths[segment]
+                );
+                destination += lengths[segment];
+            }
+        }
+
+        Generated::new(almanac)
+    }
+
+    // Day 10, a single loop over `columns` columns amid random pipes
+    pub fn pipe_maze(&mut self, columns: usize) -> Generated {
+        let spread = (columns as isize / 8).max(2);
+        let columns = (0..columns.max(1))
+            .map(|_| {
+                (
+                    self.rng.gen_range(0..8),
+                    self.rng.gen_range(0..=spread),
+                    self.rng.gen_range(0..=spread),
+                )
+            })
+            .collect::<Vec<_>>();
+        let points = lattice_loop(&columns, self.rng.gen(), 1);
+        let start = self.rng.gen_range(0..points.len());
+
+        let (maze, tiles) = pipe_maze(&points, start, || {
+            *['|', '-', 'L', 'J', '7', 'F', '.']
+                .choose(&mut self.rng)
+                .unwrap()
+        });
+
+        Generated {
+            input: maze,
+            answers: [Some((tiles.len() / 2).to_string()), None],
+        }
+    }
+
+    // Day 12, `lines` records whose groups each have their own run of '.' separated springs,
+    // too short to hold two groups. The arrangements of a record are then the product of the
+    // ways to place each group in its run
+    pub fn spring_records(&mut self, lines: usize) -> Generated {
+        let mut records = Vec::new();
+        let mut total = 0;
+
+        for _ in 0..lines {
+            let shortest = self.rng.gen_range(1..=3);
+            let groups = (0..self.rng.gen_range(1..=6))
+                .map(|_| self.rng.gen_range(shortest..=2 * shortest))
+                .collect::<Vec<_>>();
+            let mut springs = ".".repeat(self.rng.gen_range(0..=2));
+            let mut arrangements = 1;
+
+            for &group in &groups {
+                let length = self.rng.gen_range(group..=2 * shortest);
+                let mut run = vec!['?'; length];
+                if self.rng.gen_bool(0.3) {
+                    run[self.rng.gen_range(0..length)] = '#';
+                }
+
+                let broken = (0..length).filter(|&i| run[i] == '#').collect::<Vec<_>>();
+                arrangements *= (0..=length - group)
+                    .filter(|&start| broken.iter().all(|&i| (start..start + group).contains(&i)))
+                    .count();
+
+                springs.extend(run);
+                springs += &".".repeat(self.rng.gen_range(1..=2));
+            }
+
+            let groups = groups.iter().map(usize::to_string).collect::<Vec<_>>();
+            records.push(format!("{springs} {}", groups.join(",")));
+            total += arrangements;
+        }
+
+        Generated {
+            input: records.join("\n"),
+            answers: [Some(total.to_string()), None],
+        }
+    }
+
+    // Day 17
+    pub fn heat_map(&mut self, width: usize, height: usize) -> Generated {
+        let rows = (0..height.max(1))
+            .map(|_| {
+                (0..width.max(1))
+                    .map(|_| char::from(b'0' + self.rng.gen_range(1..=9)))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+
+        Generated::new(rows.join("\n"))
+    }
+
+    // Day 19, workflows only going to later ones, so every part ends up accepted or rejected
+    pub fn workflows(&mut self, workflows: usize, parts: usize) -> Generated {
+        let workflows = workflows.max(1);
+        let name = |i: usize| match i {
+            0 => String::from("in"),
+            _ => format!("w{i}"),
+        };
+        let mut lines = Vec::new();
+
+        for i in 0..workflows {
+            let target = |rng: &mut ChaCha8Rng| match rng.gen_range(i + 1..=workflows) {
+                next if next == workflows => String::from(if rng.gen() { "A" } else { "R" }),
+                next => name(next),
+            };
+            let mut rules = Vec::new();
+
+            for _ in 0..self.rng.gen_range(1..=4) {
+                let rating = ['x', 'm', 'a', 's'][self.rng.gen_range(0..4)];
+                let sign = if self.rng.gen() { '>' } else { '<' };
+                let value = self.rng.gen_range(1..=4000);
+                rules.push(format!("{rating}{sign}{value}:{}", target(&mut self.rng)));
+            }
+            rules.push(target(&mut self.rng));
+
+            lines.push(format!("{}{{{}}}", name(i), rules.join(",")));
+        }
+
+        lines.push(String::new());
+        for _ in 0..parts {
+            let [x, m, a, s] = [(); 4].map(|_| self.rng.gen_range(1..=4000));
+            lines.push(format!("{{x={x},m={m},a={a},s={s}}}"));
+        }
+
+        Generated::new(lines.join("\n"))
+    }
+
+    // Day 20, four counters of `bits` bits like in the real input. rx is turned on when they
+    // all wrap around at once, so after the LCM of their periods
+    pub fn module_network(&mut self, bits: usize) -> Generated {
+        let bits = bits.clamp(2, 20);
+        let counters = (0..4)
+            .map(|_| {
+                (
+                    bits,
+                    (1 << (bits - 1)) + 1 + 2 * self.rng.gen_range(0..1 << (bits - 2)),
+                )
+            })
+            .collect::<Vec<_>>();
+        let presses = counters
+            .iter()
+            .map(|&(_, period)| period)
+            .reduce(num::integer::lcm)
+            .unwrap();
+
+        Generated {
+            input: counter_network(&counters),
+            answers: [None, Some(presses.to_string())],
+        }
+    }
+}
+
+// (anchor, below, above) of one of the columns making a loop, see `lattice_loop`
+pub type Column = (isize, isize, isize);
+
+// A simple closed loop on the integer lattice, as the unit steps between consecutive points.
+// Built as the outline of columns of cells where neighbouring columns overlap, so it never
+// touches itself, optionally transposed and scaled. Coordinates are never negative
+pub fn lattice_loop(columns: &[Column], transposed: bool, scale: isize) -> Vec<(isize, isize)> {
+    let mut cells = HashSet::new();
+    let (mut low, mut high) = (0, 1);
+
+    for (x, &(anchor, below, above)) in columns.iter().enumerate() {
+        // Keep a cell in common with the previous column
+        let anchor = low + anchor % (high - low);
+        low = (anchor - below).max(0);
+        high = anchor + 1 + above;
+
+        for y in low..high {
+            let cell = (x as isize, y);
+            cells.insert(if transposed { (cell.1, cell.0) } else { cell });
+        }
+    }
+
+    let corners = outline(&cells)
+        .into_iter()
+        .map(|(x, y)| (x * scale, y * scale))
+        .collect::<Vec<_>>();
+
+    fill_steps(&corners)
+}
+
+// The corners around a set of cells, in order. Cells must not touch only by a corner
+fn outline(cells: &HashSet<(isize, isize)>) -> Vec<(isize, isize)> {
+    let mut neighbours: HashMap<(isize, isize), Vec<(isize, isize)>> = HashMap::new();
+    let mut add_edge = |a: (isize, isize), b: (isize, isize)| {
+        neighbours.entry(a).or_default().push(b);
+        neighbours.entry(b).or_default().push(a);
+    };
+
+    for &(x, y) in cells {
+        if !cells.contains(&(x, y - 1)) {
+            add_edge((x, y), (x + 1, y));
+        }
+        if !cells.contains(&(x, y + 1)) {
+            add_edge((x, y + 1), (x + 1, y + 1));
+        }
+        if !cells.contains(&(x - 1, y)) {
+            add_edge((x, y), (x, y + 1));
+        }
+        if !cells.contains(&(x + 1, y)) {
+            add_edge((x + 1, y), (x + 1, y + 1));
+        }
+    }
+
+    let start = *neighbours.keys().min().unwrap();
+    let mut points = vec![start];
+    let mut previous = start;
+    let mut current = neighbours[&start][0];
+
+    while current != start {
+        points.push(current);
+        let next = *neighbours[&current]
+            .iter()
+            .find(|&&n| n != previous)
+            .unwrap();
+        previous = current;
+        current = next;
+    }
+
+    points
+}
+
+// Adds the points between corners that are further than one step apart
+fn fill_steps(corners: &[(isize, isize)]) -> Vec<(isize, isize)> {
+    let mut points = Vec::new();
+
+    for (i, &(x, y)) in corners.iter().enumerate() {
+        let (next_x, next_y) = corners[(i + 1) % corners.len()];
+        let steps = (next_x - x).abs().max((next_y - y).abs());
+
+        for step in 0..steps {
+            points.push((
+                x + (next_x - x).signum() * step,
+                y + (next_y - y).signum() * step,
+            ));
+        }
+    }
+
+    points
+}
+
+fn pipe(tile: (usize, usize), a: (usize, usize), b: (usize, usize)) -> char {
+    let direction =
+        |(x, y): (usize, usize)| (x as isize - tile.0 as isize, y as isize - tile.1 as isize);
+    let mut directions = [direction(a), direction(b)];
+    directions.sort();
+
+    match directions {
+        [(0, -1), (0, 1)] => '|',
+        [(-1, 0), (1, 0)] => '-',
+        [(0, -1), (1, 0)] => 'L',
+        [(-1, 0), (0, -1)] => 'J',
+        [(-1, 0), (0, 1)] => '7',
+        [(0, 1), (1, 0)] => 'F',
+        _ => unreachable!(),
+    }
+}
+
+// The loop drawn in pipes with 'S' on its `start`th tile, amid the pipes from `junk`, and the
+// tiles of the loop. The tiles next to 'S' that aren't in the loop are ground, so 'S' only
+// connects to the loop like in the real maze
+pub fn pipe_maze(
+    points: &[(isize, isize)],
+    start: usize,
+    mut junk: impl FnMut() -> char,
+) -> (String, Vec<(usize, usize)>) {
+    let tiles = points
+        .iter()
+        .map(|&(x, y)| (x as usize + 1, y as usize + 1))
+        .collect::<Vec<_>>();
+    let width = tiles.iter().map(|tile| tile.0).max().unwrap() + 2;
+    let height = tiles.iter().map(|tile| tile.1).max().unwrap() + 2;
+
+    let mut maze = (0..height)
+        .map(|_| (0..width).map(|_| junk()).collect())
+        .collect::<Vec<Vec<_>>>();
+
+    let n = tiles.len();
+    for (i, &(x, y)) in tiles.iter().enumerate() {
+        maze[y][x] = pipe((x, y), tiles[(i + n - 1) % n], tiles[(i + 1) % n]);
+    }
+
+    let (x, y) = tiles[start];
+    let in_loop = tiles.iter().collect::<HashSet<_>>();
+    for neighbour in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+        if !in_loop.contains(&neighbour) {
+            maze[neighbour.1][neighbour.0] = '.';
+        }
+    }
+    maze[y][x] = 'S';
+
+    let maze = maze
+        .iter()
+        .map(|line| line.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    (maze, tiles)
+}
+
+// Binary counters from (bits, odd period) pairs, each counting presses up to its period.
+// Flip-flops are the bits, and the hub conjunction watches the bits set in the period. When
+// they all are, it sets the rest of the bits and the carry takes the counter back to 0.
+// The inverters of the hubs all go to sq, which turns rx on
+pub fn counter_network(counters: &[(usize, usize)]) -> String {
+    let mut lines = Vec::new();
+    let mut starts = Vec::new();
+
+    for (c, &(bits, period)) in counters.iter().enumerate() {
+        let (hub, inverter) = (format!("h{c}"), format!("i{c}"));
+        let mut hub_targets = vec![inverter.clone()];
+
+        for bit in 0..bits {
+            let mut targets = Vec::new();
+            if bit + 1 < bits {
+                targets.push(format!("f{c}{}", bit + 1));
+            }
+            if period >> bit & 1 == 1 {
+                targets.push(hub.clone());
+            }
+            if bit == 0 || period >> bit & 1 == 0 {
+                hub_targets.push(format!("f{c}{bit}"));
+            }
+            lines.push(format!("%f{c}{bit} -> {}", targets.join(", ")));
+        }
+
+        lines.push(format!("&{hub} -> {}", hub_targets.join(", ")));
+        lines.push(format!("&{inverter} -> sq"));
+        starts.push(format!("f{c}0"));
+    }
+
+    lines.push(format!("broadcaster -> {}", starts.join(", ")));
+    lines.push(String::from("&sq -> rx"));
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::explain::Explain;
+    use crate::registry;
+
+    #[test]
+    fn same_seed_same_input() {
+        for day in DAYS {
+            assert_eq!(generate(day, 7, 12), generate(day, 7, 12), "day {day}");
+            assert_ne!(generate(day, 7, 12), generate(day, 8, 12), "day {day}");
+        }
+        assert_eq!(None, generate(1, 7, 12));
+    }
+
+    #[test]
+    fn valid_with_known_answers() {
+        let registry = registry();
+
+        for day in DAYS {
+            let entry = registry
+                .entries()
+                .iter()
+                .find(|entry| entry.puzzle.day == day)
+                .unwrap();
+
+            for seed in 0..4 {
+                let generated = generate(day, seed, 12).unwrap();
+
+                for (part, known) in (1..).zip(&generated.answers) {
+                    let answer = entry
+                        .run(&generated.input, part, &mut Explain::off())
+                        .unwrap();
+                    if known.is_some() {
+                        assert_eq!(known, &answer, "day {day} seed {seed} part {part}");
+                    }
+                }
+            }
+        }
+    }
+}
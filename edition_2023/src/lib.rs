@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate lazy_static;
 
-pub mod common;
+pub use aoc_common as common;
 
 pub mod day_01;
 pub mod day_02;
@@ -23,4 +23,71 @@ pub mod day_17;
 pub mod day_18;
 pub mod day_19;
 pub mod day_20;
-pub mod day_21;
\ No newline at end of file
+pub mod day_21;
+pub mod generate;
+pub mod nonogram;
+
+#[cfg(test)]
+mod strategies;
+
+use common::solution::{Puzzle, Registry, Visitor};
+
+const RES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res");
+pub const ANSWERS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/answers.toml");
+
+// Every solved day, in order
+pub fn visit_solutions(visitor: &mut impl Visitor) {
+    let puzzle = |day| Puzzle {
+        year: 2023,
+        day,
+        res_dir: RES_DIR,
+    };
+
+    visitor.visit::<day_01::Day01>(puzzle(1));
+    visitor.visit::<day_02::Day02>(puzzle(2));
+    visitor.visit::<day_03::Day03>(puzzle(3));
+    visitor.visit::<day_04::Day04>(puzzle(4));
+    visitor.visit::<day_05::Day05>(puzzle(5));
+    visitor.visit::<day_06::Day06>(puzzle(6));
+    visitor.visit::<day_07::Day07>(puzzle(7));
+    visitor.visit::<day_08::Day08>(puzzle(8));
+    visitor.visit::<day_09::Day09>(puzzle(9));
+    visitor.visit::<day_10::Day10>(puzzle(10));
+    visitor.visit::<day_11::Day11>(puzzle(11));
+    visitor.visit::<day_12::Day12>(puzzle(12));
+    visitor.visit::<day_13::Day13>(puzzle(13));
+    visitor.visit::<day_14::Day14>(puzzle(14));
+    visitor.visit::<day_15::Day15>(puzzle(15));
+    visitor.visit::<day_16::Day16>(puzzle(16));
+    visitor.visit::<day_17::Day17>(puzzle(17));
+    visitor.visit::<day_18::Day18>(puzzle(18));
+    visitor.visit::<day_19::Day19>(puzzle(19));
+    visitor.visit::<day_20::Day20>(puzzle(20));
+    visitor.visit::<day_21::Day21>(puzzle(21));
+}
+
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    visit_solutions(&mut registry);
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use common::check::Manifest;
+
+    #[test]
+    fn manifest_covers_every_day() {
+        let manifest = Manifest::load(Path::new(ANSWERS)).unwrap();
+
+        for entry in registry().entries() {
+            let Puzzle { year, day, .. } = entry.puzzle;
+
+            assert!(manifest.get(year, day, 1).is_some(), "{year} day {day}");
+            assert!(entry.puzzle.input_path().exists(), "{year} day {day}");
+        }
+    }
+}
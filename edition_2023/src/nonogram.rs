@@ -0,0 +1,388 @@
+// Nonograms: a picture hidden in a grid, where each row and each column gives the lengths of its
+// runs of filled cells. Every line is a day 12 record, so the spring counter tells which of its
+// cells are forced. Forcing lines in turn until nothing changes solves most puzzles, the rest
+// need a cell guessed and the forcing started again
+
+use std::fmt;
+
+use crate::{
+    common::{
+        input,
+        parse::{number, ParseError, ParseResult},
+    },
+    day_12::{Record, SpringType},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nonogram {
+    rows: Vec<Vec<usize>>,
+    columns: Vec<Vec<usize>>,
+}
+
+// Filled cells are broken springs, empty ones functional
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    cells: Vec<Vec<SpringType>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solutions {
+    None,
+    Unique(Grid),
+    // The first two found, which is enough to tell
+    Many(Grid, Grid),
+}
+
+impl Solutions {
+    pub fn is_unique(&self) -> bool {
+        matches!(self, Self::Unique(_))
+    }
+}
+
+impl Nonogram {
+    pub fn new(rows: Vec<Vec<usize>>, columns: Vec<Vec<usize>>) -> Self {
+        Self { rows, columns }
+    }
+
+    // The row clues, a blank line, then the column clues. One line per clue, like 1,3 or 0
+    pub fn parse(clues: &str) -> ParseResult<Self> {
+        let sections = input::sections(clues);
+        let [rows, columns] = sections.as_slice() else {
+            return Err(ParseError::new(
+                "Expected the row and the column clues separated by a blank line",
+            ));
+        };
+
+        let parse_clues = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|line| {
+                    let clue = line
+                        .split(',')
+                        .map(|run| number(run.trim()))
+                        .collect::<ParseResult<Vec<usize>>>()?;
+
+                    Ok(clue.into_iter().filter(|&run| run > 0).collect())
+                })
+                .collect::<ParseResult<Vec<_>>>()
+        };
+
+        Ok(Self::new(parse_clues(rows)?, parse_clues(columns)?))
+    }
+
+    // The clues a picture gives
+    pub fn describe(grid: &Grid) -> Self {
+        let runs = |line: Vec<SpringType>| {
+            line.split(|&cell| cell != SpringType::Broken)
+                .map(<[SpringType]>::len)
+                .filter(|&len| len > 0)
+                .collect()
+        };
+
+        Self::new(
+            (0..grid.height()).map(|y| runs(grid.row(y))).collect(),
+            (0..grid.width()).map(|x| runs(grid.column(x))).collect(),
+        )
+    }
+
+    pub fn rows(&self) -> &[Vec<usize>] {
+        &self.rows
+    }
+
+    pub fn columns(&self) -> &[Vec<usize>] {
+        &self.columns
+    }
+
+    pub fn blank(&self) -> Grid {
+        Grid {
+            cells: vec![vec![SpringType::Unknown; self.columns.len()]; self.rows.len()],
+        }
+    }
+
+    // Fills every cell forced by its row or its column, until no line has anything more to
+    // say. False when some line has no arrangement left
+    pub fn propagate(&self, grid: &mut Grid) -> bool {
+        if grid.height() != self.rows.len() || grid.width() != self.columns.len() {
+            return false;
+        }
+
+        let mut rows_to_check = vec![true; self.rows.len()];
+        let mut columns_to_check = vec![true; self.columns.len()];
+
+        while rows_to_check.contains(&true) || columns_to_check.contains(&true) {
+            for (y, clue) in self.rows.iter().enumerate() {
+                if !std::mem::take(&mut rows_to_check[y]) {
+                    continue;
+                }
+
+                let Some(forced) = Record::new(grid.row(y), clue.clone()).forced() else {
+                    return false;
+                };
+
+                for (x, cell) in forced.into_iter().enumerate() {
+                    if grid.fill(x, y, cell) {
+                        columns_to_check[x] = true;
+                    }
+                }
+            }
+
+            for (x, clue) in self.columns.iter().enumerate() {
+                if !std::mem::take(&mut columns_to_check[x]) {
+                    continue;
+                }
+
+                let Some(forced) = Record::new(grid.column(x), clue.clone()).forced() else {
+                    return false;
+                };
+
+                for (y, cell) in forced.into_iter().enumerate() {
+                    if grid.fill(x, y, cell) {
+                        rows_to_check[y] = true;
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    pub fn solve(&self) -> Solutions {
+        let mut found = Vec::new();
+        self.search(self.blank(), &mut found);
+
+        let mut found = found.into_iter();
+        match (found.next(), found.next()) {
+            (None, _) => Solutions::None,
+            (Some(grid), None) => Solutions::Unique(grid),
+            (Some(first), Some(second)) => Solutions::Many(first, second),
+        }
+    }
+
+    fn search(&self, mut grid: Grid, found: &mut Vec<Grid>) {
+        if found.len() == 2 || !self.propagate(&mut grid) {
+            return;
+        }
+
+        let Some((x, y)) = grid.first_unknown() else {
+            found.push(grid);
+            return;
+        };
+
+        for guess in [SpringType::Broken, SpringType::Functional] {
+            let mut guessed = grid.clone();
+            guessed.cells[y][x] = guess;
+            self.search(guessed, found);
+        }
+    }
+}
+
+impl Grid {
+    // # for filled, . for empty and ? for not known yet
+    pub fn parse(picture: &str) -> ParseResult<Self> {
+        let cells = input::lines(picture)
+            .map(|line| line.chars().map(SpringType::parse).collect())
+            .collect::<ParseResult<Vec<Vec<_>>>>()?;
+
+        if cells.windows(2).any(|pair| pair[0].len() != pair[1].len()) {
+            return Err(ParseError::new("Expected rows of the same width"));
+        }
+
+        Ok(Self { cells })
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<SpringType> {
+        self.cells.get(y)?.get(x).copied()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.first_unknown().is_none()
+    }
+
+    fn row(&self, y: usize) -> Vec<SpringType> {
+        self.cells[y].clone()
+    }
+
+    fn column(&self, x: usize) -> Vec<SpringType> {
+        self.cells.iter().map(|row| row[x]).collect()
+    }
+
+    // Whether the cell was unknown and now isn't
+    fn fill(&mut self, x: usize, y: usize, cell: SpringType) -> bool {
+        let current = &mut self.cells[y][x];
+        let filled = *current == SpringType::Unknown && cell != SpringType::Unknown;
+
+        if filled {
+            *current = cell;
+        }
+        filled
+    }
+
+    fn first_unknown(&self) -> Option<(usize, usize)> {
+        self.cells.iter().enumerate().find_map(|(y, row)| {
+            row.iter()
+                .position(|&cell| cell == SpringType::Unknown)
+                .map(|x| (x, y))
+        })
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.cells.iter().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for cell in row {
+                let c = match cell {
+                    SpringType::Broken => '#',
+                    SpringType::Functional => '.',
+                    SpringType::Unknown => '?',
+                };
+                write!(f, "{c}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn picture() -> impl Strategy<Value = Grid> {
+        (1..=6_usize, 1..=6_usize)
+            .prop_flat_map(|(width, height)| {
+                prop::collection::vec(prop::collection::vec(any::<bool>(), width), height)
+            })
+            .prop_map(|rows| {
+                let picture = rows
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|&filled| if filled { '#' } else { '.' })
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<_>>();
+
+                Grid::parse(&picture.join("\n")).unwrap()
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn solves_to_the_same_clues(picture in picture()) {
+            let nonogram = Nonogram::describe(&picture);
+
+            match nonogram.solve() {
+                Solutions::None => prop_assert!(false, "no solution for\n{}", picture),
+                Solutions::Unique(solution) => prop_assert_eq!(picture, solution),
+                Solutions::Many(first, second) => {
+                    prop_assert_ne!(&first, &second);
+                    prop_assert_eq!(&nonogram, &Nonogram::describe(&first));
+                    prop_assert_eq!(&nonogram, &Nonogram::describe(&second));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unique() {
+        let clues = "\
+        3
+        1,1
+        5
+        1,1
+        1,1
+
+        4
+        1,1
+        1,1
+        1,1
+        4";
+
+        let picture = "\
+        .###.
+        #...#
+        #####
+        #...#
+        #...#";
+
+        let nonogram = Nonogram::parse(clues).unwrap();
+        let picture = Grid::parse(picture).unwrap();
+        assert_eq!(Nonogram::describe(&picture), nonogram);
+
+        let solutions = nonogram.solve();
+        assert!(solutions.is_unique());
+        assert_eq!(Solutions::Unique(picture), solutions);
+    }
+
+    #[test]
+    fn propagation_alone() {
+        // The 5 forces the middle row, then every column has only one way left
+        let nonogram = Nonogram::parse("1\n5\n1\n\n1\n1\n3\n1\n1").unwrap();
+        let mut grid = nonogram.blank();
+
+        assert!(nonogram.propagate(&mut grid));
+        assert!(grid.is_solved());
+        assert_eq!("..#..\n#####\n..#..", grid.to_string());
+    }
+
+    #[test]
+    fn guessing() {
+        // Either diagonal: no line forces anything
+        let nonogram = Nonogram::parse("1\n1\n\n1\n1").unwrap();
+        let mut grid = nonogram.blank();
+
+        assert!(nonogram.propagate(&mut grid));
+        assert_eq!("??\n??", grid.to_string());
+        assert_eq!(
+            Solutions::Many(
+                Grid::parse("#.\n.#").unwrap(),
+                Grid::parse(".#\n#.").unwrap()
+            ),
+            nonogram.solve()
+        );
+    }
+
+    #[test]
+    fn impossible() {
+        assert_eq!(
+            Solutions::None,
+            Nonogram::parse("2\n0\n\n1\n0").unwrap().solve()
+        );
+        assert_eq!(
+            Solutions::None,
+            Nonogram::parse("3\n\n1\n1").unwrap().solve()
+        );
+    }
+
+    #[test]
+    fn wide() {
+        // The row alone has more arrangements than a usize holds, the columns pick one
+        let picture = Grid::parse(&"#...".repeat(25)).unwrap();
+        let nonogram = Nonogram::describe(&picture);
+        assert_eq!(vec![vec![1; 25]], nonogram.rows());
+
+        assert_eq!(Solutions::Unique(picture), nonogram.solve());
+    }
+
+    #[test]
+    fn bad_clues() {
+        assert!(Nonogram::parse("1,1\n2").is_err());
+        assert!(Nonogram::parse("1,x\n\n1").is_err());
+        assert!(Grid::parse("#.\n#").is_err());
+        assert!(Grid::parse("#o").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+// Random inputs shared by the property tests comparing days against naive oracles
+
+use proptest::prelude::*;
+
+use crate::generate::lattice_loop;
+
+// A simple closed loop on the integer lattice, as the unit steps between consecutive points
+pub fn rectilinear_loop(
+    max_columns: usize,
+    max_scale: isize,
+) -> impl Strategy<Value = Vec<(isize, isize)>> {
+    (
+        prop::collection::vec((0..8_isize, 0..3_isize, 0..3_isize), 1..=max_columns),
+        any::<bool>(),
+        1..=max_scale,
+    )
+        .prop_map(|(columns, transposed, scale)| lattice_loop(&columns, transposed, scale))
+}
+
+// Cells of a width x height grid reachable from the border without crossing a wall
+pub fn flood_outside(
+    width: usize,
+    height: usize,
+    is_wall: impl Fn(usize, usize) -> bool,
+) -> Vec<Vec<bool>> {
+    let mut outside = vec![vec![false; width]; height];
+    let mut stack = Vec::new();
+
+    for x in 0..width {
+        stack.extend([(x, 0), (x, height - 1)]);
+    }
+    for y in 0..height {
+        stack.extend([(0, y), (width - 1, y)]);
+    }
+
+    while let Some((x, y)) = stack.pop() {
+        if outside[y][x] || is_wall(x, y) {
+            continue;
+        }
+        outside[y][x] = true;
+
+        if x > 0 {
+            stack.push((x - 1, y));
+        }
+        if y > 0 {
+            stack.push((x, y - 1));
+        }
+        if x + 1 < width {
+            stack.push((x + 1, y));
+        }
+        if y + 1 < height {
+            stack.push((x, y + 1));
+        }
+    }
+
+    outside
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn loops_are_simple_and_closed(points in rectilinear_loop(8, 3)) {
+            let distinct = points.iter().collect::<HashSet<_>>();
+            prop_assert_eq!(points.len(), distinct.len());
+
+            for (i, &(x, y)) in points.iter().enumerate() {
+                let (next_x, next_y) = points[(i + 1) % points.len()];
+                prop_assert_eq!(1, (next_x - x).abs() + (next_y - y).abs());
+            }
+        }
+    }
+}
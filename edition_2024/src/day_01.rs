@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseResult, Required},
+    solution::{or_overflow, Solution},
+};
+
+pub fn part_one(lists: &str) -> usize {
+    Lists::parse(lists).unwrap().total_distance()
+}
+
+pub fn part_two(lists: &str) -> Option<usize> {
+    Lists::parse(lists).unwrap().similarity_score()
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    type Parsed<'a> = Lists;
+
+    fn parse(lists: &str) -> ParseResult<Self::Parsed<'_>> {
+        Lists::parse(lists)
+    }
+
+    fn part_one(lists: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        lists.total_distance().to_string()
+    }
+
+    fn part_two(lists: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(or_overflow(lists.similarity_score()))
+    }
+}
+
+#[derive(Debug)]
+pub struct Lists {
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+impl Lists {
+    fn parse(lists: &str) -> ParseResult<Self> {
+        let (left, right) = input::lines(lists)
+            .map(|line| {
+                // Ids fit in 32 bits, so the distances between them add up without overflowing
+                let mut ids = line.split_whitespace();
+                let left = number::<u32>(ids.next().required("the left id")?)?;
+                let right = number::<u32>(ids.next().required("the right id")?)?;
+                Ok((left as usize, right as usize))
+            })
+            .collect::<ParseResult<Vec<(usize, usize)>>>()?
+            .into_iter()
+            .unzip();
+
+        Ok(Self { left, right })
+    }
+
+    // Pairs the smallest ids of both lists, then the second smallest and so on
+    fn total_distance(&self) -> usize {
+        let mut left = self.left.clone();
+        let mut right = self.right.clone();
+        left.sort_unstable();
+        right.sort_unstable();
+
+        left.iter().zip(&right).map(|(l, r)| l.abs_diff(*r)).sum()
+    }
+
+    // None when it doesn't fit
+    fn similarity_score(&self) -> Option<usize> {
+        let mut appearances = HashMap::new();
+        for id in &self.right {
+            *appearances.entry(id).or_insert(0) += 1;
+        }
+
+        self.left.iter().try_fold(0_usize, |score, id| {
+            score.checked_add(id.checked_mul(*appearances.get(id).unwrap_or(&0))?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let lists = "\
+        3   4
+        4   3
+        2   5
+        1   3
+        3   9
+        3   3";
+
+        assert_eq!(11, part_one(lists));
+        assert_eq!(Some(31), part_two(lists));
+    }
+
+    #[test]
+    fn overflow() {
+        let id = u32::MAX;
+        let lists = format!("{id} 1\n{id} {id}\n");
+
+        assert_eq!(id as usize - 1, part_one(&lists));
+        assert_eq!(Some(id as usize * 2), part_two(&lists));
+
+        // Each of the many copies on the left appears as often on the right
+        let lists = format!("{id} {id}\n").repeat(1 << 17);
+        assert_eq!(None, part_two(&lists));
+        assert_eq!(
+            "overflow",
+            Day01::part_two(&Day01::parse(&lists).unwrap(), &mut Explain::off()).unwrap()
+        );
+        assert!(Day01::parse("4294967296 1").is_err());
+    }
+
+    #[test]
+    fn real() {
+        let lists = include_str!("../res/day_01.txt");
+
+        assert_eq!(1320851, part_one(lists));
+        assert_eq!(Some(26859182), part_two(lists));
+    }
+}
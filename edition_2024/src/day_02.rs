@@ -0,0 +1,117 @@
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{numbers, ParseResult},
+    solution::Solution,
+};
+
+pub fn part_one(reports: &str) -> usize {
+    count_safe(&parse_reports(reports).unwrap(), Report::is_safe)
+}
+
+pub fn part_two(reports: &str) -> usize {
+    count_safe(&parse_reports(reports).unwrap(), Report::is_safe_dampened)
+}
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    type Parsed<'a> = Vec<Report>;
+
+    fn parse(reports: &str) -> ParseResult<Self::Parsed<'_>> {
+        parse_reports(reports)
+    }
+
+    fn part_one(reports: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        count_safe(reports, Report::is_safe).to_string()
+    }
+
+    fn part_two(reports: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(count_safe(reports, Report::is_safe_dampened).to_string())
+    }
+}
+
+fn parse_reports(reports: &str) -> ParseResult<Vec<Report>> {
+    input::lines(reports).map(Report::parse).collect()
+}
+
+fn count_safe(reports: &[Report], is_safe: impl Fn(&Report) -> bool) -> usize {
+    reports.iter().filter(|report| is_safe(report)).count()
+}
+
+#[derive(Debug)]
+pub struct Report {
+    levels: Vec<isize>,
+}
+
+impl Report {
+    // Levels fit in 32 bits, so the differences between them can't overflow
+    fn parse(line: &str) -> ParseResult<Self> {
+        Ok(Self {
+            levels: numbers::<i32>(line)?
+                .into_iter()
+                .map(|level| level as isize)
+                .collect(),
+        })
+    }
+
+    fn is_safe(&self) -> bool {
+        levels_safe(self.levels.iter().copied())
+    }
+
+    // Safe after removing at most one level
+    fn is_safe_dampened(&self) -> bool {
+        self.is_safe()
+            || (0..self.levels.len()).any(|removed| {
+                levels_safe(
+                    self.levels
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| *i != removed)
+                        .map(|(_, level)| *level),
+                )
+            })
+    }
+}
+
+// All increasing or all decreasing, by 1 to 3 at each step
+fn levels_safe(levels: impl Iterator<Item = isize>) -> bool {
+    let levels = levels.collect::<Vec<_>>();
+    let direction = levels
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).signum())
+        .next()
+        .unwrap_or(1);
+
+    levels.windows(2).all(|pair| {
+        let difference = pair[1] - pair[0];
+        difference.signum() == direction && (1..=3).contains(&difference.abs())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let reports = "\
+        7 6 4 2 1
+        1 2 7 8 9
+        9 7 6 2 1
+        1 3 2 4 5
+        8 6 4 4 1
+        1 3 6 7 9";
+
+        assert_eq!(2, part_one(reports));
+        assert_eq!(4, part_two(reports));
+    }
+
+    #[test]
+    fn real() {
+        let reports = include_str!("../res/day_02.txt");
+
+        assert_eq!(379, part_one(reports));
+        assert_eq!(430, part_two(reports));
+    }
+}
@@ -0,0 +1,106 @@
+use regex::Regex;
+
+use crate::common::{explain::Explain, parse::ParseResult, solution::Solution};
+
+pub fn part_one(memory: &str) -> usize {
+    sum_products(&Instruction::parse_all(memory), false)
+}
+
+pub fn part_two(memory: &str) -> usize {
+    sum_products(&Instruction::parse_all(memory), true)
+}
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    type Parsed<'a> = Vec<Instruction>;
+
+    // Anything that isn't an instruction is corrupted memory, so every input parses
+    fn parse(memory: &str) -> ParseResult<Self::Parsed<'_>> {
+        Ok(Instruction::parse_all(memory))
+    }
+
+    fn part_one(instructions: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        sum_products(instructions, false).to_string()
+    }
+
+    fn part_two(instructions: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(sum_products(instructions, true).to_string())
+    }
+}
+
+// With conditionals, `don't()` disables the multiplications that follow until the next `do()`
+fn sum_products(instructions: &[Instruction], conditionals: bool) -> usize {
+    let mut enabled = true;
+    let mut sum = 0;
+
+    for instruction in instructions {
+        match instruction {
+            Instruction::Mul(a, b) if enabled || !conditionals => sum += a * b,
+            Instruction::Mul(_, _) => {}
+            Instruction::Do => enabled = true,
+            Instruction::Dont => enabled = false,
+        }
+    }
+
+    sum
+}
+
+lazy_static! {
+    static ref INSTRUCTION_REGEX: Regex =
+        Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)|do\(\)|don't\(\)").unwrap();
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Mul(usize, usize),
+    Do,
+    Dont,
+}
+
+impl Instruction {
+    // The valid instructions in the corrupted memory, everything else is ignored
+    fn parse_all(memory: &str) -> Vec<Self> {
+        INSTRUCTION_REGEX
+            .captures_iter(memory)
+            .map(|caps| match &caps[0] {
+                "do()" => Instruction::Do,
+                "don't()" => Instruction::Dont,
+                _ => Instruction::Mul(caps[1].parse().unwrap(), caps[2].parse().unwrap()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let memory = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+        assert_eq!(161, part_one(memory));
+
+        let memory = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(
+            vec![
+                Instruction::Mul(2, 4),
+                Instruction::Dont,
+                Instruction::Mul(5, 5),
+                Instruction::Mul(11, 8),
+                Instruction::Do,
+                Instruction::Mul(8, 5),
+            ],
+            Instruction::parse_all(memory)
+        );
+        assert_eq!(48, part_two(memory));
+    }
+
+    #[test]
+    fn real() {
+        let memory = include_str!("../res/day_03.txt");
+
+        assert_eq!(188741603, part_one(memory));
+        assert_eq!(67269798, part_two(memory));
+    }
+}
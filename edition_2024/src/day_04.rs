@@ -0,0 +1,122 @@
+use crate::common::{explain::Explain, input, parse::ParseResult, solution::Solution};
+
+const XMAS: [char; 4] = ['X', 'M', 'A', 'S'];
+
+pub fn part_one(puzzle: &str) -> usize {
+    WordSearch::parse(puzzle).count_xmas()
+}
+
+pub fn part_two(puzzle: &str) -> usize {
+    WordSearch::parse(puzzle).count_x_mas()
+}
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    type Parsed<'a> = WordSearch;
+
+    // Any letters make a word search, lines of different lengths included
+    fn parse(puzzle: &str) -> ParseResult<Self::Parsed<'_>> {
+        Ok(WordSearch::parse(puzzle))
+    }
+
+    fn part_one(search: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        search.count_xmas().to_string()
+    }
+
+    fn part_two(search: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(search.count_x_mas().to_string())
+    }
+}
+
+#[derive(Debug)]
+pub struct WordSearch {
+    letters: Vec<Vec<char>>,
+}
+
+impl WordSearch {
+    fn parse(puzzle: &str) -> Self {
+        Self {
+            letters: input::lines(puzzle)
+                .map(|line| line.chars().collect())
+                .collect(),
+        }
+    }
+
+    fn get(&self, x: isize, y: isize) -> Option<char> {
+        let row = self.letters.get(usize::try_from(y).ok()?)?;
+        row.get(usize::try_from(x).ok()?).copied()
+    }
+
+    fn positions(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        self.letters
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| (0..row.len()).map(move |x| (x as isize, y as isize)))
+    }
+
+    // XMAS written in any of the 8 directions, overlaps included
+    fn count_xmas(&self) -> usize {
+        let directions = (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&direction| direction != (0, 0))
+            .collect::<Vec<_>>();
+
+        self.positions()
+            .map(|(x, y)| {
+                directions
+                    .iter()
+                    .filter(|(dx, dy)| {
+                        (0..XMAS.len() as isize)
+                            .all(|i| self.get(x + i * dx, y + i * dy) == Some(XMAS[i as usize]))
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    // Two MAS crossing on their A, each one written forwards or backwards
+    fn count_x_mas(&self) -> usize {
+        let is_mas = |a, b| matches!((a, b), (Some('M'), Some('S')) | (Some('S'), Some('M')));
+
+        self.positions()
+            .filter(|&(x, y)| {
+                self.get(x, y) == Some('A')
+                    && is_mas(self.get(x - 1, y - 1), self.get(x + 1, y + 1))
+                    && is_mas(self.get(x + 1, y - 1), self.get(x - 1, y + 1))
+            })
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let puzzle = "
+            MMMSXXMASM
+            MSAMXMSMSA
+            AMXSXMAAMM
+            MSAMASMSMX
+            XMASAMXAMM
+            XXAMMXXAMA
+            SMSMSASXSS
+            SAXAMASAAA
+            MAMMMXMMMM
+            MXMXAXMASX
+        ";
+
+        assert_eq!(18, part_one(puzzle));
+        assert_eq!(9, part_two(puzzle));
+    }
+
+    #[test]
+    fn real() {
+        let puzzle = include_str!("../res/day_04.txt");
+
+        assert_eq!(2557, part_one(puzzle));
+        assert_eq!(1854, part_two(puzzle));
+    }
+}
@@ -0,0 +1,185 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use crate::common::{
+    explain::Explain,
+    input,
+    parse::{number, ParseError, ParseResult, Required},
+    solution::Solution,
+};
+
+pub fn part_one(manual: &str) -> usize {
+    Manual::parse(manual).unwrap().sum_ordered_middles()
+}
+
+pub fn part_two(manual: &str) -> usize {
+    Manual::parse(manual).unwrap().sum_reordered_middles()
+}
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    type Parsed<'a> = Manual;
+
+    fn parse(manual: &str) -> ParseResult<Self::Parsed<'_>> {
+        Manual::parse(manual)
+    }
+
+    fn part_one(manual: &Self::Parsed<'_>, _: &mut Explain) -> String {
+        manual.sum_ordered_middles().to_string()
+    }
+
+    fn part_two(manual: &Self::Parsed<'_>, _: &mut Explain) -> Option<String> {
+        Some(manual.sum_reordered_middles().to_string())
+    }
+}
+
+// Pages fit in 32 bits, so the middle ones add up without overflowing
+fn page(text: &str) -> ParseResult<usize> {
+    Ok(number::<u32>(text)? as usize)
+}
+
+#[derive(Debug)]
+pub struct Manual {
+    // (before, after) page pairs
+    rules: HashSet<(usize, usize)>,
+    updates: Vec<Vec<usize>>,
+}
+
+impl Manual {
+    fn parse(manual: &str) -> ParseResult<Self> {
+        let sections = input::sections(manual);
+        let [rules, updates] = sections.as_slice() else {
+            return Err(ParseError::new(
+                "Expected the rules and the updates separated by a blank line",
+            ));
+        };
+
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let (before, after) = rule.split_once('|').required("the '|' in a rule")?;
+                Ok((page(before)?, page(after)?))
+            })
+            .collect::<ParseResult<_>>()?;
+        let updates = updates
+            .iter()
+            .map(|update| update.split(',').map(page).collect())
+            .collect::<ParseResult<_>>()?;
+
+        let manual = Self { rules, updates };
+        if !manual.updates.iter().all(|update| manual.is_total(update)) {
+            return Err(ParseError::new(
+                "Expected the rules to put the pages of every update in a single order",
+            ));
+        }
+        Ok(manual)
+    }
+
+    // When the rules order every pair of pages in the update without a loop, the pages have
+    // 0, 1, .. n - 1 others before them, which sorting relies on
+    fn is_total(&self, update: &[usize]) -> bool {
+        let mut before = update
+            .iter()
+            .map(|a| {
+                let less = |b: &&usize| self.compare(b, a) == Ordering::Less;
+                update.iter().filter(less).count()
+            })
+            .collect::<Vec<_>>();
+        before.sort_unstable();
+        !update.is_empty() && before.into_iter().eq(0..update.len())
+    }
+
+    // Rules only relate pages that are both in the update, and every such pair has one
+    fn compare(&self, a: &usize, b: &usize) -> Ordering {
+        if self.rules.contains(&(*a, *b)) {
+            Ordering::Less
+        } else if self.rules.contains(&(*b, *a)) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    fn is_ordered(&self, update: &[usize]) -> bool {
+        update.is_sorted_by(|a, b| self.compare(a, b) != Ordering::Greater)
+    }
+
+    fn sum_ordered_middles(&self) -> usize {
+        self.updates
+            .iter()
+            .filter(|update| self.is_ordered(update))
+            .map(|update| update[update.len() / 2])
+            .sum()
+    }
+
+    fn sum_reordered_middles(&self) -> usize {
+        self.updates
+            .iter()
+            .filter(|update| !self.is_ordered(update))
+            .map(|update| {
+                let mut update = update.clone();
+                update.sort_by(|a, b| self.compare(a, b));
+                update[update.len() / 2]
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let manual = "
+            47|53
+            97|13
+            97|61
+            97|47
+            75|29
+            61|13
+            75|53
+            29|13
+            97|29
+            53|29
+            61|53
+            97|53
+            61|29
+            47|13
+            75|47
+            97|75
+            47|61
+            75|61
+            47|29
+            75|13
+            53|13
+
+            75,47,61,53,29
+            97,61,53,29,13
+            75,29,13
+            75,97,47,61,53
+            61,13,29
+            97,13,75,29,47
+        ";
+
+        assert_eq!(143, part_one(manual));
+        assert_eq!(123, part_two(manual));
+    }
+
+    #[test]
+    fn real() {
+        let manual = include_str!("../res/day_05.txt");
+
+        assert_eq!(5964, part_one(manual));
+        assert_eq!(4719, part_two(manual));
+    }
+
+    #[test]
+    fn loops() {
+        assert!(Manual::parse("1|2\n2|3\n3|1\n\n1,2,3").is_err());
+        assert!(Manual::parse("1|2\n\n1,2,3").is_err());
+        assert!(Manual::parse("1|2\n\n1,2,1").is_err());
+        assert!(Manual::parse("1|2\n2|3\n1|3\n\n3,1,2").is_ok());
+    }
+}
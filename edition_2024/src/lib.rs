@@ -0,0 +1,56 @@
+#[macro_use]
+extern crate lazy_static;
+
+pub use aoc_common as common;
+
+pub mod day_01;
+pub mod day_02;
+pub mod day_03;
+pub mod day_04;
+pub mod day_05;
+
+use common::solution::{Puzzle, Registry, Visitor};
+
+const RES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res");
+pub const ANSWERS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/answers.toml");
+
+// Every solved day, in order
+pub fn visit_solutions(visitor: &mut impl Visitor) {
+    let puzzle = |day| Puzzle {
+        year: 2024,
+        day,
+        res_dir: RES_DIR,
+    };
+
+    visitor.visit::<day_01::Day01>(puzzle(1));
+    visitor.visit::<day_02::Day02>(puzzle(2));
+    visitor.visit::<day_03::Day03>(puzzle(3));
+    visitor.visit::<day_04::Day04>(puzzle(4));
+    visitor.visit::<day_05::Day05>(puzzle(5));
+}
+
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    visit_solutions(&mut registry);
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use common::check::Manifest;
+
+    #[test]
+    fn manifest_covers_every_day() {
+        let manifest = Manifest::load(Path::new(ANSWERS)).unwrap();
+
+        for entry in registry().entries() {
+            let Puzzle { year, day, .. } = entry.puzzle;
+
+            assert!(manifest.get(year, day, 1).is_some(), "{year} day {day}");
+            assert!(entry.puzzle.input_path().exists(), "{year} day {day}");
+        }
+    }
+}
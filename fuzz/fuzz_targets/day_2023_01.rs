@@ -0,0 +1,12 @@
+#![no_main]
+
+use aoc_common::{explain::Explain, solution::Solution};
+use edition_2023::day_01::Day01;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(parsed) = Day01::parse(input) {
+        Day01::part_one(&parsed, &mut Explain::off());
+        Day01::part_two(&parsed, &mut Explain::off());
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use aoc_common::{explain::Explain, solution::Solution};
+use edition_2023::day_02::Day02;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(parsed) = Day02::parse(input) {
+        Day02::part_one(&parsed, &mut Explain::off());
+        Day02::part_two(&parsed, &mut Explain::off());
+    }
+});
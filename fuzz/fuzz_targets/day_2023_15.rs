@@ -0,0 +1,12 @@
+#![no_main]
+
+use aoc_common::{explain::Explain, solution::Solution};
+use edition_2023::day_15::Day15;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(parsed) = Day15::parse(input) {
+        Day15::part_one(&parsed, &mut Explain::off());
+        Day15::part_two(&parsed, &mut Explain::off());
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use aoc_common::{explain::Explain, solution::Solution};
+use edition_2024::day_05::Day05;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(parsed) = Day05::parse(input) {
+        Day05::part_one(&parsed, &mut Explain::off());
+        Day05::part_two(&parsed, &mut Explain::off());
+    }
+});
@@ -0,0 +1,76 @@
+// Every day is benchmarked on its real input, with parsing and each part measured on their own.
+// To compare a change against the current code:
+//   cargo bench --bench days -- --save-baseline before
+//   (apply the change)
+//   cargo bench --bench days -- --baseline before
+// A single day can be selected with a filter, e.g. `cargo bench --bench days -- 2023_day_12`
+
+use std::fs;
+use std::hint::black_box;
+use std::time::Duration;
+
+use aoc_common::explain::Explain;
+use aoc_common::solution::{Puzzle, Solution, Visitor};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+// Parts taking from hundreds of milliseconds to seconds, run with fewer samples
+const HEAVY: [(u16, u8, u8); 6] = [
+    (2023, 5, 2),
+    (2023, 12, 2),
+    (2023, 16, 2),
+    (2023, 17, 1),
+    (2023, 17, 2),
+    (2023, 21, 1),
+];
+
+struct Bencher<'a> {
+    criterion: &'a mut Criterion,
+}
+
+impl Visitor for Bencher<'_> {
+    fn visit<S: Solution>(&mut self, puzzle: Puzzle) {
+        let path = puzzle.input_path();
+        let input = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Could not read {}: {e}", path.display()));
+        let name = format!("{}_day_{:02}", puzzle.year, puzzle.day);
+
+        self.criterion
+            .bench_function(&format!("{name}/parse"), |b| {
+                b.iter(|| S::parse(black_box(&input)))
+            });
+
+        let parsed =
+            S::parse(&input).unwrap_or_else(|e| panic!("Invalid input {}: {e}", path.display()));
+        let has_part_two = S::part_two(&parsed, &mut Explain::off()).is_some();
+
+        for part in 1..=2 {
+            if part == 2 && !has_part_two {
+                continue;
+            }
+
+            let mut group = self.criterion.benchmark_group(&name);
+            if HEAVY.contains(&(puzzle.year, puzzle.day, part)) {
+                group
+                    .sample_size(10)
+                    .measurement_time(Duration::from_secs(20));
+            }
+
+            group.bench_function(format!("part_{part}"), |b| {
+                b.iter(|| match part {
+                    1 => Some(S::part_one(black_box(&parsed), &mut Explain::off())),
+                    _ => S::part_two(black_box(&parsed), &mut Explain::off()),
+                })
+            });
+            group.finish();
+        }
+    }
+}
+
+fn days(criterion: &mut Criterion) {
+    let mut bencher = Bencher { criterion };
+    edition_2023::visit_solutions(&mut bencher);
+    edition_2024::visit_solutions(&mut bencher);
+}
+
+criterion_group!(benches, days);
+criterion_main!(benches);
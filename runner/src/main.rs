@@ -0,0 +1,265 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use aoc_common::check::{check_entry, Manifest, Outcome, Status};
+use aoc_common::explain::Explain;
+use aoc_common::inputs::{default_cache_dir, HttpSource, Inputs};
+use aoc_common::page::{check_examples, generate_test, PuzzlePage};
+use aoc_common::solution::Registry;
+
+const USAGE: &str = "usage:
+    runner check [--manifest <path>] [--year <year>] [--explain] [day...]
+    runner examples <year> <day> [--generate] [--page <path>] [--explain]
+    runner fetch <year> <day>
+    runner generate <year> <day> [--seed <seed>] [--size <size>]
+    runner submit <year> <day> <part> [answer]";
+
+const MANIFESTS: [&str; 2] = [edition_2023::ANSWERS, edition_2024::ANSWERS];
+
+// The solutions of every edition
+fn registry() -> Registry {
+    let mut registry = Registry::new();
+    edition_2023::visit_solutions(&mut registry);
+    edition_2024::visit_solutions(&mut registry);
+    registry
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    match args.split_first() {
+        Some((command, rest)) if command == "check" => check(rest),
+        Some((command, rest)) if command == "examples" => examples(rest),
+        Some((command, rest)) if command == "fetch" => fetch(rest),
+        Some((command, rest)) if command == "generate" => generate(rest),
+        Some((command, rest)) if command == "submit" => submit(rest),
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Runs every selected day against its stored input and compares with the manifest.
+// Fails when any answer is wrong, missing answers are only reported
+fn check(args: &[String]) -> ExitCode {
+    let mut manifest_paths = MANIFESTS.map(PathBuf::from).to_vec();
+    let mut year = None;
+    let mut explain = false;
+    let mut days = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--manifest" => manifest_paths = vec![args.next().expect(USAGE).into()],
+            "--year" => year = Some(number::<u16>(args.next())),
+            "--explain" => explain = true,
+            day => days.push(day.parse::<u8>().expect(USAGE)),
+        }
+    }
+
+    let mut manifest = Manifest::default();
+    for path in manifest_paths {
+        match Manifest::load(&path) {
+            Ok(loaded) => manifest.merge(loaded),
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let registry = registry();
+    let mut counts = [0; 4];
+
+    for entry in registry.entries() {
+        if year.is_some_and(|year| year != entry.puzzle.year)
+            || !days.is_empty() && !days.contains(&entry.puzzle.day)
+        {
+            continue;
+        }
+
+        for outcome in check_entry(entry, &manifest, explain) {
+            report(&outcome, &mut counts);
+        }
+    }
+
+    summary(counts)
+}
+
+fn summary(counts: [usize; 4]) -> ExitCode {
+    let [passed, failed, missing, no_input] = counts;
+    println!("\n{passed} passed, {failed} failed, {missing} missing, {no_input} without input");
+
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn report(outcome: &Outcome, counts: &mut [usize; 4]) {
+    let (index, status) = match &outcome.status {
+        Status::Pass => (0, "pass".to_string()),
+        Status::Fail { expected } => (1, format!("FAIL (expected {expected})")),
+        Status::Missing => (2, "missing".to_string()),
+        Status::NoInput => (3, "no input".to_string()),
+        Status::Invalid { error } => (1, format!("INVALID INPUT ({error})")),
+    };
+    counts[index] += 1;
+
+    println!(
+        "{} day {:02} part {}  {:>16}  {:>10.2?}  {status}",
+        outcome.puzzle.year,
+        outcome.puzzle.day,
+        outcome.part,
+        outcome.answer.as_deref().unwrap_or("-"),
+        outcome.elapsed,
+    );
+    for step in &outcome.steps {
+        println!("    {step}");
+    }
+}
+
+// Checks the solution against the examples of its saved description page, or prints them as a
+// test to paste in the day's module
+fn examples(args: &[String]) -> ExitCode {
+    let (year, day) = (number(args.first()), number(args.get(1)));
+    let mut generate = false;
+    let mut explain = false;
+    let mut page_path = None;
+    let mut args = args.iter().skip(2);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--generate" => generate = true,
+            "--page" => page_path = Some(PathBuf::from(args.next().expect(USAGE))),
+            "--explain" => explain = true,
+            _ => panic!("{USAGE}"),
+        }
+    }
+
+    let registry = registry();
+    let entry = registry
+        .get(year, day)
+        .unwrap_or_else(|| panic!("No solution for {year} day {day}"));
+    let page = PuzzlePage::load(&page_path.unwrap_or_else(|| entry.puzzle.page_path()));
+
+    if generate {
+        print!("{}", generate_test(&format!("Day{day:02}"), &page));
+        return ExitCode::SUCCESS;
+    }
+
+    let mut counts = [0; 4];
+    for outcome in check_examples(entry, &page, explain) {
+        report(&outcome, &mut counts);
+    }
+
+    summary(counts)
+}
+
+// Prints a random input for the day, the answers known from building it go to stderr
+fn generate(args: &[String]) -> ExitCode {
+    let (year, day) = (number::<u16>(args.first()), number(args.get(1)));
+    let (mut seed, mut size) = (0, 100);
+    let mut args = args.iter().skip(2);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => seed = number(args.next()),
+            "--size" => size = number(args.next()),
+            _ => panic!("{USAGE}"),
+        }
+    }
+
+    let generated = match year {
+        2023 => edition_2023::generate::generate(day, seed, size),
+        _ => None,
+    };
+    let Some(generated) = generated else {
+        eprintln!("No generator for {year} day {day}");
+        return ExitCode::FAILURE;
+    };
+
+    println!("{}", generated.input);
+    for (part, answer) in (1..).zip(generated.answers) {
+        if let Some(answer) = answer {
+            eprintln!("part {part}: {answer}");
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn inputs() -> Inputs<HttpSource> {
+    let source = HttpSource::from_env().unwrap_or_else(|e| panic!("{e}"));
+    Inputs::new(default_cache_dir(), source)
+}
+
+fn number<T: std::str::FromStr>(arg: Option<&String>) -> T {
+    arg.and_then(|arg| arg.parse().ok()).expect(USAGE)
+}
+
+fn fetch(args: &[String]) -> ExitCode {
+    let (year, day) = (number(args.first()), number(args.get(1)));
+    let inputs = inputs();
+
+    match inputs.input(year, day) {
+        Ok(_) => {
+            println!("{}", inputs.path(year, day).display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Could not fetch {year} day {day}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Without an answer, the registered solution is run on the cached input and its answer sent
+fn submit(args: &[String]) -> ExitCode {
+    let (year, day, part) = (
+        number(args.first()),
+        number(args.get(1)),
+        number(args.get(2)),
+    );
+    let inputs = inputs();
+
+    let answer = match args.get(3) {
+        Some(answer) => answer.clone(),
+        None => {
+            let registry = registry();
+            let entry = registry
+                .get(year, day)
+                .unwrap_or_else(|| panic!("No solution for {year} day {day}"));
+            let input = inputs
+                .input(year, day)
+                .unwrap_or_else(|e| panic!("Could not fetch {year} day {day}: {e}"));
+
+            entry
+                .run(&input, part, &mut Explain::off())
+                .unwrap_or_else(|e| panic!("Invalid input for {year} day {day}: {e}"))
+                .unwrap_or_else(|| panic!("{year} day {day} has no part {part}"))
+        }
+    };
+
+    match inputs.submit(year, day, part, &answer) {
+        Ok(outcome) => {
+            let note = if outcome.sent {
+                ""
+            } else {
+                " (known, not sent)"
+            };
+            println!(
+                "{year} day {day:02} part {part}  {answer}  {}{note}",
+                outcome.verdict
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Could not submit {year} day {day} part {part}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}